@@ -1,5 +1,4 @@
 import logging
-import os
 import tempfile
 import weakref
 from contextlib import contextmanager
@@ -8,14 +7,12 @@ from typing import Any, Iterator, Optional, Tuple, Union
 
 import numpy as np
 
-from .pyarraypool import ShmObjectPool
+from .pyarraypool import MAX_DTYPE_LEN, MAX_NDIM, ShmObjectPool
 
 MemorySizeType = Union[str, int]
 
 LOGGER = logging.getLogger(__name__)
 
-_MAX_PYTHON_ID = np.iinfo(np.int64).max
-
 _GLOBAL_POOL: Optional[ShmObjectPool] = None
 _CFG_LINK_PATH = f"{tempfile.gettempdir()}/pyarraypool.seg"
 _CFG_SLOT_COUNT: int = 10_000
@@ -53,17 +50,26 @@ def _parse_datasize_to_bytes(value: MemorySizeType) -> int:
 
 class ndarrayproxy(np.ndarray):
     python_id: int = 0
+    generation: int = 0
+    described: bool = False
 
     def __reduce__(self) -> Tuple[Any, ...]:
         # Check: https://docs.python.org/3/library/pickle.html#object.__reduce__
         if self.python_id == 0:
             raise ValueError("Cannot transfer none registered object (did you try sending slice ?)")
 
+        if self.described:
+            # Shape and dtype are stored in pool
+            args: Tuple[Any, ...] = (self.python_id, self.generation)
+        else:
+            # Array cannot be described in pool, dtype is pickled as is
+            args = (self.python_id, self.generation, self.shape, self.dtype)
+
         return (
             # Builder
             ndarrayproxy._shm_reconstruct,
             # Builder args
-            (self.shape, self.dtype, self.python_id),
+            args,
         )
 
     def __getstate__(self):
@@ -73,44 +79,77 @@ class ndarrayproxy(np.ndarray):
         raise NotImplementedError()
 
     @classmethod
-    def _shm_reconstruct(cls, shape, dtype, python_id) -> "ndarrayproxy":
+    def _shm_reconstruct(cls, python_id, generation, shape=None, dtype=None) -> "ndarrayproxy":
         pool = get_reusable_pool()
-        memview = pool.attach_object(python_id)
-
-        out = cls(shape, dtype=dtype, buffer=memview)
+        memview = pool.attach_object(python_id, generation)
 
-        weakref.finalize(out, pool.detach_object, python_id)
+        if shape is not None:
+            out = cls(shape, dtype=dtype, buffer=memview)
+        else:
+            descriptor = pool.describe(python_id)
+            if descriptor is None:
+                pool.detach_object(python_id, generation)
+                raise ValueError("Cannot reconstruct object without array descriptor", python_id)
+
+            out = cls(
+                descriptor["shape"],
+                dtype=np.dtype(descriptor["dtype"]),
+                buffer=memview,
+                strides=descriptor["strides"],
+            )
+            out.described = True
+
+        weakref.finalize(out, pool.detach_object, python_id, generation)
         return out
 
 
-def make_transferable(arr: np.ndarray, *, transfer_required: bool = True) -> ndarrayproxy:
-    python_id = hash((id(arr), os.getpid())) % _MAX_PYTHON_ID
+def _c_contiguous_strides(shape: Tuple[int, ...], itemsize: int) -> Tuple[int, ...]:
+    strides = []
+    stride = itemsize
+    for dim in reversed(shape):
+        strides.append(stride)
+        stride *= dim
+    return tuple(reversed(strides))
+
 
+def _is_describable(arr: np.ndarray) -> bool:
+    # Structured dtypes cannot be rebuilt from `dtype.str` (field names are lost)
+    dtype_str = arr.dtype.str
+    return (
+        arr.ndim <= MAX_NDIM
+        and len(dtype_str) <= MAX_DTYPE_LEN
+        and np.dtype(dtype_str) == arr.dtype
+    )
+
+
+def make_transferable(arr: np.ndarray, *, transfer_required: bool = True) -> ndarrayproxy:
     pool = get_reusable_pool()
 
-    if pool.memview_of(python_id) is None:
-        # Check if object is already registered in pool
-        memview = pool.add_object(python_id, arr.size * arr.itemsize)
-        data_not_set = True
+    # Pool issues a new ID, so each call creates an independent object
+    described = _is_describable(arr)
+    if described:
+        python_id, generation, memview = pool.allocate_array(
+            arr.size * arr.itemsize,
+            arr.dtype.str,
+            arr.shape,
+            _c_contiguous_strides(arr.shape, arr.itemsize),
+            arr.dtype.byteorder,
+        )
     else:
-        # Otherwise attach to memory view
-        memview = pool.attach_object(python_id)
-        assert memview.nbytes == arr.size * arr.itemsize
-        data_not_set = False
+        python_id, generation, memview = pool.allocate(arr.size * arr.itemsize)
 
-    # Create proxy object
+    # Create proxy object and set data
     out = ndarrayproxy(arr.shape, dtype=arr.dtype, buffer=memview)
     out.python_id = python_id
-
-    # Set data
-    if data_not_set:
-        out[:] = arr[:]
+    out.generation = generation
+    out.described = described
+    out[:] = arr[:]
 
     # Mark object as transferable free
     if not transfer_required:
-        pool.set_object_releasable(python_id)
+        pool.set_object_releasable(python_id, generation)
 
-    weakref.finalize(out, pool.detach_object, python_id)
+    weakref.finalize(out, pool.detach_object, python_id, generation)
     return out
 
 
@@ -1,29 +1,118 @@
-from typing import Optional
+from typing import Any, Dict, List, Literal, Optional, Sequence, Tuple, Union
 
+MAX_NDIM: int
+MAX_DTYPE_LEN: int
 
 class ShmObjectPool:
     def __init__(
         self, *,
         slot_count: int = 5000,
         data_size: int = 524288000,
+        alignment: int = 64,
+        strategy: Literal["first_fit", "best_fit", "segregated"] = "first_fit",
+        auto_compact: bool = False,
+        max_segment_count: int = 1,
+        name_count: int = 256,
+        lock_timeout: float = 10.0,
         path: str = "pyarraypool.seg",
     ) -> None:
         ...
 
-    def add_object(self, python_id: int, request_size: int) -> memoryview:
+    def add_object(
+        self, python_id: int, request_size: int, alignment: Optional[int] = None
+    ) -> memoryview:
         ...
 
-    def attach_object(self, python_id: int) -> memoryview:
+    def add_object_blocking(self, python_id: int, request_size: int, timeout: float) -> memoryview:
         ...
 
-    def detach_object(self, python_id: int) -> None:
+    def add_array(
+        self,
+        python_id: int,
+        request_size: int,
+        dtype: str,
+        shape: Sequence[int],
+        strides: Sequence[int],
+        byte_order: Literal["=", "<", ">", "|"] = "=",
+    ) -> memoryview:
         ...
 
-    def set_object_releasable(self, python_id: int) -> None:
+    def allocate(
+        self, request_size: int, alignment: Optional[int] = None
+    ) -> Tuple[int, int, memoryview]:
+        ...
+
+    def allocate_array(
+        self,
+        request_size: int,
+        dtype: str,
+        shape: Sequence[int],
+        strides: Sequence[int],
+        byte_order: Literal["=", "<", ">", "|"] = "=",
+    ) -> Tuple[int, int, memoryview]:
+        ...
+
+    def add_named(self, name: str, request_size: int) -> Tuple[int, int, memoryview]:
+        ...
+
+    def attach_named(self, name: str) -> Tuple[int, int, memoryview]:
+        ...
+
+    def lookup(self, name: str) -> Optional[Tuple[int, int]]:
+        ...
+
+    def names(self) -> List[str]:
+        ...
+
+    def generation_of(self, python_id: int) -> int:
+        ...
+
+    def describe(self, python_id: int) -> Optional[Dict[str, Any]]:
+        ...
+
+    def attach_object(self, python_id: int, generation: int = 0) -> memoryview:
+        ...
+
+    def attach_object_wait(self, python_id: int, timeout: float, generation: int = 0) -> memoryview:
+        ...
+
+    def detach_object(self, python_id: int, generation: int = 0) -> None:
+        ...
+
+    def add_objects(self, objects: List[Tuple[int, int]]) -> List[memoryview]:
+        ...
+
+    def attach_objects(
+        self,
+        python_ids: List[int],
+        generations: Optional[List[int]] = None,
+    ) -> List[memoryview]:
+        ...
+
+    def detach_objects(
+        self,
+        python_ids: List[int],
+        generations: Optional[List[int]] = None,
+    ) -> None:
+        ...
+
+    def set_object_releasable(self, python_id: int, generation: int = 0) -> None:
+        ...
+
+    def resize_object(self, python_id: int, new_size: int) -> memoryview:
         ...
 
     def memview_of(self, python_id: int) -> Optional[memoryview]:
         ...
 
+    def compact(self) -> int:
+        ...
+
+    def reclaim_dead_processes(self) -> int:
+        ...
+
+    def stats(self) -> Dict[str, Union[int, float]]:
+        ...
+
     def dump(self) -> str:
         ...
@@ -0,0 +1,208 @@
+/*! Typed array descriptor stored alongside objects. */
+
+use std::str;
+
+use crate::memory_info::ArrayPoolError;
+
+/// Maximum number of dimensions of a described array.
+pub const MAX_NDIM: usize = 8;
+
+/// Maximum length in bytes of dtype string.
+pub const MAX_DTYPE_LEN: usize = 16;
+
+/// Size in bytes of a descriptor stored in pool.
+pub const DESCRIPTOR_SIZE: usize = std::mem::size_of::<ArrayDescriptor>();
+
+/// Byte order of array items (same characters as numpy `dtype.byteorder`).
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[repr(u8)]
+pub enum ByteOrder {
+    /// Native byte order of platform.
+    #[default]
+    Native = b'=',
+
+    /// Little endian.
+    Little = b'<',
+
+    /// Big endian.
+    Big = b'>',
+
+    /// Byte order is not relevant for item type.
+    NotApplicable = b'|',
+}
+
+impl ByteOrder {
+    /// Get byte order from its raw value.
+    pub const fn from_raw(value: u8) -> Option<Self> {
+        match value {
+            b'=' => Some(Self::Native),
+            b'<' => Some(Self::Little),
+            b'>' => Some(Self::Big),
+            b'|' => Some(Self::NotApplicable),
+            _ => None,
+        }
+    }
+
+    /// Get byte order raw value.
+    pub const fn as_raw(self) -> u8 {
+        self as u8
+    }
+}
+
+/// Description of a typed array stored in pool.
+///
+/// Fields have a fixed width so descriptor can be read by any process.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(C)]
+pub struct ArrayDescriptor {
+    shape: [u64; MAX_NDIM],
+    strides: [i64; MAX_NDIM],
+    dtype: [u8; MAX_DTYPE_LEN],
+    dtype_len: u8,
+    ndim: u8,
+    byte_order: u8,
+    _padding: [u8; 5],
+}
+
+// Descriptors are part of segment format (see `doc/SEGMENT_FORMAT.md`)
+const _: () = {
+    use std::mem::{offset_of, size_of};
+    assert!(offset_of!(ArrayDescriptor, strides) == 64);
+    assert!(offset_of!(ArrayDescriptor, dtype) == 128);
+    assert!(offset_of!(ArrayDescriptor, dtype_len) == 144);
+    assert!(size_of::<ArrayDescriptor>() == 152);
+};
+
+impl ArrayDescriptor {
+    /// Create new descriptor.
+    pub fn new(
+        dtype: &str,
+        byte_order: ByteOrder,
+        shape: &[u64],
+        strides: &[i64],
+    ) -> Result<Self, ArrayPoolError> {
+        if dtype.is_empty()
+            || dtype.len() > MAX_DTYPE_LEN
+            || shape.len() > MAX_NDIM
+            || shape.len() != strides.len()
+        {
+            return Err(ArrayPoolError::InvalidDescriptor);
+        }
+
+        let mut descriptor = Self {
+            shape: [0; MAX_NDIM],
+            strides: [0; MAX_NDIM],
+            dtype: [0; MAX_DTYPE_LEN],
+            dtype_len: dtype.len() as u8,
+            ndim: shape.len() as u8,
+            byte_order: byte_order.as_raw(),
+            _padding: [0; 5],
+        };
+        descriptor.shape[..shape.len()].copy_from_slice(shape);
+        descriptor.strides[..strides.len()].copy_from_slice(strides);
+        descriptor.dtype[..dtype.len()].copy_from_slice(dtype.as_bytes());
+
+        Ok(descriptor)
+    }
+
+    /// Check descriptor read from pool contains valid data.
+    pub fn valid(&self) -> Result<(), ArrayPoolError> {
+        if self.dtype_len as usize > MAX_DTYPE_LEN
+            || self.ndim as usize > MAX_NDIM
+            || ByteOrder::from_raw(self.byte_order).is_none()
+            || str::from_utf8(&self.dtype[..self.dtype_len as usize]).is_err()
+        {
+            Err(ArrayPoolError::InvalidDescriptor)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Get item type (as numpy dtype string).
+    pub fn dtype(&self) -> &str {
+        str::from_utf8(&self.dtype[..self.dtype_len as usize]).unwrap_or_default()
+    }
+
+    /// Get byte order of items.
+    pub fn byte_order(&self) -> ByteOrder {
+        ByteOrder::from_raw(self.byte_order).unwrap_or_default()
+    }
+
+    /// Get number of dimensions.
+    pub fn ndim(&self) -> usize {
+        self.ndim as usize
+    }
+
+    /// Get size of each dimension.
+    pub fn shape(&self) -> &[u64] {
+        &self.shape[..self.ndim()]
+    }
+
+    /// Get strides in bytes of each dimension.
+    pub fn strides(&self) -> &[i64] {
+        &self.strides[..self.ndim()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new() {
+        let descriptor =
+            ArrayDescriptor::new("<f8", ByteOrder::Little, &[10, 20], &[160, 8]).unwrap();
+
+        assert_eq!(descriptor.valid(), Ok(()));
+        assert_eq!(descriptor.dtype(), "<f8");
+        assert_eq!(descriptor.byte_order(), ByteOrder::Little);
+        assert_eq!(descriptor.ndim(), 2);
+        assert_eq!(descriptor.shape(), [10, 20]);
+        assert_eq!(descriptor.strides(), [160, 8]);
+    }
+
+    #[test]
+    fn test_new_invalid() {
+        assert_eq!(
+            ArrayDescriptor::new("", ByteOrder::Native, &[], &[]),
+            Err(ArrayPoolError::InvalidDescriptor)
+        );
+        assert_eq!(
+            ArrayDescriptor::new("a_very_long_dtype_name", ByteOrder::Native, &[], &[]),
+            Err(ArrayPoolError::InvalidDescriptor)
+        );
+        assert_eq!(
+            ArrayDescriptor::new("u1", ByteOrder::Native, &[1; 9], &[1; 9]),
+            Err(ArrayPoolError::InvalidDescriptor)
+        );
+        assert_eq!(
+            ArrayDescriptor::new("u1", ByteOrder::Native, &[1, 2], &[1]),
+            Err(ArrayPoolError::InvalidDescriptor)
+        );
+    }
+
+    #[test]
+    fn test_valid() {
+        let mut descriptor = ArrayDescriptor::new("u1", ByteOrder::Native, &[4], &[1]).unwrap();
+
+        descriptor.byte_order = b'x';
+        assert_eq!(descriptor.valid(), Err(ArrayPoolError::InvalidDescriptor));
+
+        descriptor.byte_order = b'|';
+        descriptor.ndim = 42;
+        assert_eq!(descriptor.valid(), Err(ArrayPoolError::InvalidDescriptor));
+    }
+
+    #[test]
+    fn test_byte_order_raw_value() {
+        for byte_order in [
+            ByteOrder::Native,
+            ByteOrder::Little,
+            ByteOrder::Big,
+            ByteOrder::NotApplicable,
+        ] {
+            assert_eq!(ByteOrder::from_raw(byte_order.as_raw()), Some(byte_order));
+        }
+        assert_eq!(ByteOrder::from_raw(b'x'), None);
+    }
+}
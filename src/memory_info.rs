@@ -1,9 +1,18 @@
 /*! Helper to manage memory block. */
 
-use std::{fmt, process};
+use std::{
+    fmt, process,
+    sync::atomic::{AtomicU64, Ordering},
+};
 
 use thiserror::Error;
 
+use crate::{
+    descriptor::DESCRIPTOR_SIZE,
+    mutex::Event,
+    object_index::{index_capacity, IndexEntry, ObjectIndex},
+};
+
 /// Possible error that can occurs with memory pool management.
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Error)]
 pub enum ArrayPoolError {
@@ -26,6 +35,109 @@ pub enum ArrayPoolError {
     /// Invalid python object ID.
     #[error("invalid python object ID")]
     InvalidPythonId,
+
+    /// Alignment is not a power of two.
+    #[error("invalid alignment: {0} (must be a power of two)")]
+    InvalidAlignment(usize),
+
+    /// Array descriptor cannot be stored or contains invalid data.
+    #[error("invalid array descriptor")]
+    InvalidDescriptor,
+
+    /// Handle generation does not match object generation.
+    #[error("stale handle (python ID: {0})")]
+    StaleHandle(PythonId),
+
+    /// Object name is empty or too long.
+    #[error("invalid object name")]
+    InvalidName,
+
+    /// Name is already used by another object.
+    #[error("object name already exists")]
+    NameAlreadyExists,
+
+    /// No object is registered with name.
+    #[error("object name cannot be found")]
+    NameNotFound,
+
+    /// No free entry left in name table.
+    #[error("name table is full")]
+    NameTableFull,
+
+    /// Same object is listed several times in a batch operation.
+    #[error("duplicate handle (python ID: {0})")]
+    DuplicateHandle(PythonId),
+
+    /// Object data cannot be moved since it is used by others.
+    #[error("object is in use (python ID: {0})")]
+    ObjectInUse(PythonId),
+
+    /// Slot table has been left inconsistent (IE. by a process killed while updating it).
+    #[error("corrupted slot table")]
+    CorruptedSlotTable,
+}
+
+/// First python ID issued by pool.
+///
+/// IDs computed by callers are usually positive `i64` hash values, starting
+/// issued IDs above this range prevent them from colliding.
+pub const FIRST_POOL_PYTHON_ID: u64 = 1 << 63;
+
+/// Default alignment of object data in bytes.
+///
+/// 64 bytes matches cache line size and is enough for any SIMD kernel.
+pub const DEFAULT_ALIGNMENT: usize = 64;
+
+/// Check alignment value can be used to place objects.
+pub const fn check_alignment(alignment: usize) -> Result<(), ArrayPoolError> {
+    if alignment.is_power_of_two() {
+        Ok(())
+    } else {
+        Err(ArrayPoolError::InvalidAlignment(alignment))
+    }
+}
+
+/// Policy used to pick a free block when adding an object to pool.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[repr(u8)]
+pub enum AllocationStrategy {
+    /// Use first free block large enough.
+    #[default]
+    FirstFit = 0,
+
+    /// Use smallest free block large enough.
+    BestFit = 1,
+
+    /// Group free blocks in power of two size classes and use first block
+    /// large enough from smallest class.
+    Segregated = 2,
+}
+
+impl AllocationStrategy {
+    /// Get strategy from its raw value.
+    pub const fn from_raw(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::FirstFit),
+            1 => Some(Self::BestFit),
+            2 => Some(Self::Segregated),
+            _ => None,
+        }
+    }
+
+    /// Get strategy raw value.
+    pub const fn as_raw(self) -> u8 {
+        self as u8
+    }
+}
+
+/// Get power of two size class of a block.
+const fn size_class(size: usize) -> u32 {
+    usize::BITS - size.leading_zeros()
+}
+
+/// Get number of bytes to add to `offset` to make it a multiple of `alignment`.
+const fn padding_for(offset: usize, alignment: usize) -> usize {
+    (alignment - offset % alignment) % alignment
 }
 
 /// Wrapper arount u64 to add and restrict python ID values.
@@ -34,7 +146,7 @@ pub enum ArrayPoolError {
 pub struct PythonId(pub u64);
 
 impl PythonId {
-    const fn valid(&self) -> Result<(), ArrayPoolError> {
+    pub(crate) const fn valid(&self) -> Result<(), ArrayPoolError> {
         if self.0 == 0 {
             Err(ArrayPoolError::InvalidPythonId)
         } else {
@@ -53,7 +165,80 @@ impl fmt::Display for PythonId {
     }
 }
 
+/// Reference to an object of pool.
+///
+/// When generation is set, handle can only be used with the object it has
+/// been created for, even if a new object later reuses the same python ID.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ObjectHandle {
+    python_id: PythonId,
+    generation: u64,
+}
+
+impl ObjectHandle {
+    /// Create new handle.
+    ///
+    /// A generation of 0 matches any generation.
+    pub const fn new(python_id: PythonId, generation: u64) -> Self {
+        Self {
+            python_id,
+            generation,
+        }
+    }
+
+    /// Get python object ID.
+    pub const fn python_id(&self) -> PythonId {
+        self.python_id
+    }
+
+    /// Get object generation.
+    pub const fn generation(&self) -> u64 {
+        self.generation
+    }
+}
+
+impl From<PythonId> for ObjectHandle {
+    fn from(python_id: PythonId) -> Self {
+        Self::new(python_id, 0)
+    }
+}
+
+/// Maximum number of processes whose references are tracked for each object.
+///
+/// References from additional processes are only counted in object refcount
+/// (untracked references), so they cannot be reclaimed if these processes die.
+/// A process missing from holder list can only drop untracked references.
+pub const MAX_HOLDERS: usize = 8;
+
 const FLAG_MEMSLOT_TRANSFERED: u8 = 0x01;
+const FLAG_MEMSLOT_DESCRIBED: u8 = 0x02;
+const FLAG_MEMSLOT_PINNED: u8 = 0x04;
+
+/// Get number of bytes stored before object data for given slot flags.
+const fn prefix_size_for(flags: u8) -> usize {
+    if flags & FLAG_MEMSLOT_DESCRIBED == FLAG_MEMSLOT_DESCRIBED {
+        DESCRIPTOR_SIZE
+    } else {
+        0
+    }
+}
+
+/// References held on an object by a process.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(C)]
+struct Holder {
+    /// Process ID (0 if entry is free).
+    pid: u32,
+
+    /// Number of references held by process.
+    count: u32,
+}
+
+impl Holder {
+    const fn empty() -> Self {
+        Self { pid: 0, count: 0 }
+    }
+}
 
 /// Store information about memory hole.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -62,11 +247,19 @@ pub struct MemorySlot {
     /// Python object ID.
     python_id: PythonId,
 
-    /// Slot size in bytes.
-    size: usize,
+    /// Slot offset in bytes from start of data region.
+    offset: u64,
+
+    /// Slot size in bytes (padding included).
+    size: u64,
+
+    /// Bytes skipped at the start of the slot to align object data.
+    ///
+    /// Array descriptor (if any) is stored between padding and object data.
+    padding: u64,
 
     /// Reference object count.
-    refcount: usize,
+    refcount: u64,
 
     /// Source process ID
     ///
@@ -76,18 +269,53 @@ pub struct MemorySlot {
     /// Associated flags:
     ///
     /// - FLAG_MEMSLOT_TRANSFERED
+    /// - FLAG_MEMSLOT_DESCRIBED
+    /// - FLAG_MEMSLOT_PINNED
     flags: u8,
+
+    /// Log2 of object data alignment.
+    ///
+    /// Used to keep object aligned when it is moved during compaction.
+    alignment_log2: u8,
+
+    /// Index of segment containing slot data.
+    segment: u16,
+
+    /// Object generation (unique for each object added to pool, 0 if slot is free).
+    generation: u64,
+
+    /// Processes holding references on object.
+    holders: [Holder; MAX_HOLDERS],
 }
 
+// Slots are part of segment format (see `doc/SEGMENT_FORMAT.md`)
+const _: () = {
+    use std::mem::{offset_of, size_of};
+    assert!(size_of::<Holder>() == 8);
+    assert!(offset_of!(MemorySlot, offset) == 8);
+    assert!(offset_of!(MemorySlot, refcount) == 32);
+    assert!(offset_of!(MemorySlot, source_pid) == 40);
+    assert!(offset_of!(MemorySlot, segment) == 46);
+    assert!(offset_of!(MemorySlot, generation) == 48);
+    assert!(offset_of!(MemorySlot, holders) == 56);
+    assert!(size_of::<MemorySlot>() == 120);
+};
+
 impl MemorySlot {
     /// Create empty slot.
     pub const fn empty() -> Self {
         Self {
             python_id: PythonId::empty(),
+            offset: 0,
             size: 0,
+            padding: 0,
             refcount: 0,
             source_pid: 0,
             flags: 0,
+            alignment_log2: 0,
+            segment: 0,
+            generation: 0,
+            holders: [Holder::empty(); MAX_HOLDERS],
         }
     }
 
@@ -95,21 +323,39 @@ impl MemorySlot {
     const fn with_size(size: usize) -> Self {
         Self {
             python_id: PythonId::empty(),
-            size,
+            offset: 0,
+            size: size as u64,
+            padding: 0,
             refcount: 0,
             source_pid: 0,
             flags: 0,
+            alignment_log2: 0,
+            segment: 0,
+            generation: 0,
+            holders: [Holder::empty(); MAX_HOLDERS],
         }
     }
 
     /// Create new slot with python ID and object size.
     fn with_object_id(python_id: PythonId, size: usize) -> Self {
+        let mut holders = [Holder::empty(); MAX_HOLDERS];
+        holders[0] = Holder {
+            pid: process::id(),
+            count: 1,
+        };
+
         Self {
             python_id,
-            size,
+            offset: 0,
+            size: size as u64,
+            padding: 0,
             refcount: 1,
             source_pid: process::id(),
             flags: 0,
+            alignment_log2: 0,
+            segment: 0,
+            generation: 0,
+            holders,
         }
     }
 
@@ -118,6 +364,21 @@ impl MemorySlot {
         self.python_id.0 == 0
     }
 
+    /// Get slot offset in bytes from start of data region.
+    const fn offset(&self) -> usize {
+        self.offset as usize
+    }
+
+    /// Get slot size in bytes (padding included).
+    const fn size(&self) -> usize {
+        self.size as usize
+    }
+
+    /// Get bytes skipped at the start of the slot to align object data.
+    const fn padding(&self) -> usize {
+        self.padding as usize
+    }
+
     /// Check if slot can be release.
     ///
     /// IE. Object has been transfered at least once between processes and is unused.
@@ -125,24 +386,116 @@ impl MemorySlot {
         (self.flags & FLAG_MEMSLOT_TRANSFERED == FLAG_MEMSLOT_TRANSFERED) && (self.refcount == 0)
     }
 
+    /// Get number of bytes stored before object data (after padding).
+    const fn prefix_size(&self) -> usize {
+        prefix_size_for(self.flags)
+    }
+
+    /// Get size of object stored in slot (padding and prefix excluded).
+    const fn object_size(&self) -> usize {
+        self.size() - self.padding() - self.prefix_size()
+    }
+
+    /// Get offset of object data from start of data region.
+    const fn data_offset(&self) -> usize {
+        self.offset() + self.padding() + self.prefix_size()
+    }
+
     /// Split block to create new free space.
     const fn split_block(&self, bytes_count: usize) -> (Self, Self) {
-        assert!(bytes_count < self.size);
+        assert!(bytes_count < self.size());
+        assert!(self.padding() <= bytes_count);
         (
             Self {
                 python_id: self.python_id,
-                size: bytes_count,
+                offset: self.offset,
+                size: bytes_count as u64,
+                padding: self.padding,
                 refcount: self.refcount,
                 source_pid: self.source_pid,
                 flags: self.flags,
+                alignment_log2: self.alignment_log2,
+                segment: self.segment,
+                generation: self.generation,
+                holders: self.holders,
+            },
+            Self {
+                offset: self.offset + bytes_count as u64,
+                segment: self.segment,
+                ..Self::with_size(self.size() - bytes_count)
             },
-            Self::with_size(self.size - bytes_count),
         )
     }
 
+    /// Set block offset.
+    fn set_offset(&mut self, offset: usize) -> Self {
+        self.offset = offset as u64;
+        *self
+    }
+
+    /// Set index of segment containing slot data.
+    fn set_segment(&mut self, segment: u16) -> Self {
+        self.segment = segment;
+        *self
+    }
+
+    /// Set alignment padding.
+    fn set_padding(&mut self, padding: usize) -> Self {
+        debug_assert!(padding <= self.size());
+        self.padding = padding as u64;
+        *self
+    }
+
+    /// Set object data alignment.
+    fn set_alignment(&mut self, alignment: usize) -> Self {
+        debug_assert!(alignment.is_power_of_two());
+        self.alignment_log2 = alignment.trailing_zeros() as u8;
+        *self
+    }
+
+    /// Get object data alignment.
+    const fn alignment(&self) -> usize {
+        1 << self.alignment_log2
+    }
+
+    /// Check if object data can be moved.
+    ///
+    /// IE. Object is still in pool, no process currently use it and no
+    /// pointer to its data has been given without a reference.
+    const fn is_movable(&self) -> bool {
+        !self.is_free() && self.refcount == 0 && self.flags & FLAG_MEMSLOT_PINNED == 0
+    }
+
     /// Set reference count.
+    ///
+    /// References are all considered as held by current process.
     fn set_refcount(&mut self, refcount: usize) -> Self {
-        self.refcount = refcount;
+        self.refcount = refcount as u64;
+        self.holders = [Holder::empty(); MAX_HOLDERS];
+        if refcount > 0 {
+            self.holders[0] = Holder {
+                pid: process::id(),
+                count: refcount as u32,
+            };
+        }
+        *self
+    }
+
+    /// Set object generation.
+    fn set_generation(&mut self, generation: u64) -> Self {
+        self.generation = generation;
+        *self
+    }
+
+    /// Set flags (in addition to existing ones).
+    fn set_flags(&mut self, flags: u8) -> Self {
+        self.flags |= flags;
+        *self
+    }
+
+    /// Prevent object data from being moved by compaction.
+    fn set_pinned(&mut self) -> Self {
+        self.flags |= FLAG_MEMSLOT_PINNED;
         *self
     }
 
@@ -158,22 +511,95 @@ impl MemorySlot {
             self.set_transfered();
         }
     }
+
+    /// Check if data may be used through another reference than the one held
+    /// by given process.
+    fn used_by_others(&self, pid: u32) -> bool {
+        self.refcount > 1
+            || self.flags & FLAG_MEMSLOT_PINNED != 0
+            || self.holders.iter().any(|x| x.pid != 0 && x.pid != pid)
+    }
+
+    /// Add a reference held by given process.
+    fn add_holder(&mut self, pid: u32) {
+        self.refcount += 1;
+
+        if let Some(holder) = self.holders.iter_mut().find(|x| x.pid == pid) {
+            holder.count += 1;
+        } else if let Some(holder) = self.holders.iter_mut().find(|x| x.pid == 0) {
+            *holder = Holder { pid, count: 1 };
+        }
+    }
+
+    /// Get number of references held by processes missing from holder list.
+    fn untracked_refcount(&self) -> u64 {
+        let tracked: u64 = self.holders.iter().map(|x| x.count as u64).sum();
+        self.refcount.saturating_sub(tracked)
+    }
+
+    /// Remove a reference held by given process.
+    fn remove_holder(&mut self, pid: u32) {
+        if let Some(holder) = self.holders.iter_mut().find(|x| x.pid == pid) {
+            holder.count -= 1;
+            if holder.count == 0 {
+                *holder = Holder::empty();
+            }
+        } else if self.untracked_refcount() == 0 {
+            return;
+        }
+        self.refcount = self.refcount.saturating_sub(1);
+    }
+
+    /// Remove references held by processes for which `is_alive` returns false.
+    ///
+    /// Returns number of removed references.
+    fn remove_dead_holders<F>(&mut self, mut is_alive: F) -> usize
+    where
+        F: FnMut(u32) -> bool,
+    {
+        let mut removed = 0;
+        for holder in self.holders.iter_mut().filter(|x| x.pid != 0) {
+            if !is_alive(holder.pid) {
+                removed += holder.count as usize;
+                *holder = Holder::empty();
+            }
+        }
+
+        self.refcount = self.refcount.saturating_sub(removed as u64);
+        removed
+    }
 }
 
 /// Contains information about existing object in memory pool.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub struct ObjectInfo {
+    segment: usize,
     offset: usize,
     size: usize,
 }
 
 impl ObjectInfo {
-    /// Create new object.
+    /// Create new object stored in first segment.
     pub fn new(offset: usize, size: usize) -> Self {
-        Self { offset, size }
+        Self {
+            segment: 0,
+            offset,
+            size,
+        }
+    }
+
+    /// Set index of segment containing object.
+    pub fn with_segment(mut self, segment: usize) -> Self {
+        self.segment = segment;
+        self
+    }
+
+    /// Get index of segment containing object.
+    pub fn segment(&self) -> usize {
+        self.segment
     }
 
-    /// Get object offset.
+    /// Get object data offset from start of data region.
     pub fn offset(&self) -> usize {
         self.offset
     }
@@ -184,54 +610,236 @@ impl ObjectInfo {
     }
 }
 
+/// Usage statistics of memory pool.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct PoolStats {
+    /// Size of data region in bytes.
+    pub total_bytes: usize,
+
+    /// Bytes used by objects (alignment padding included).
+    pub used_bytes: usize,
+
+    /// Bytes not used by any object.
+    pub free_bytes: usize,
+
+    /// Size of largest free block.
+    pub largest_free_block: usize,
+
+    /// Number of free blocks.
+    pub free_block_count: usize,
+
+    /// Number of objects in pool.
+    pub object_count: usize,
+
+    /// Number of slots describing an object or a free block.
+    pub slots_used: usize,
+
+    /// Number of slots of pool.
+    pub slot_count: usize,
+}
+
+impl PoolStats {
+    /// Get fragmentation ratio of free space.
+    ///
+    /// Value is 0 when all free bytes are in a single block and gets close to 1
+    /// when free space is spread over many small blocks.
+    pub fn fragmentation(&self) -> f64 {
+        if self.free_bytes == 0 {
+            0.0
+        } else {
+            1.0 - self.largest_free_block as f64 / self.free_bytes as f64
+        }
+    }
+}
+
 /// Vector of memory slot with associated function to manage them.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug)]
 #[repr(C)]
 pub struct MemoryPool<'a> {
     slots: &'a mut [MemorySlot],
+    index: ObjectIndex<'a>,
+    alignment: usize,
+    base_offset: usize,
+    strategy: AllocationStrategy,
+    generation_counter: Option<&'a AtomicU64>,
+    release_event: Option<&'a Event>,
 }
 
 impl<'a> MemoryPool<'a> {
     /// Create struct from already init vec.
-    pub fn new(slots: &'a mut [MemorySlot]) -> Self {
-        Self { slots }
+    ///
+    /// Objects are not aligned until [`MemoryPool::with_alignment`] is called.
+    pub fn new(slots: &'a mut [MemorySlot], index: &'a mut [IndexEntry]) -> Self {
+        Self {
+            slots,
+            index: ObjectIndex::new(index),
+            alignment: 1,
+            base_offset: 0,
+            strategy: AllocationStrategy::FirstFit,
+            generation_counter: None,
+            release_event: None,
+        }
     }
 
     /// Create new structure from uninitialized slice.
-    pub fn from_uninit_slice(slots: &'a mut [MemorySlot], data_size: usize) -> Self {
+    pub fn from_uninit_slice(
+        slots: &'a mut [MemorySlot],
+        index: &'a mut [IndexEntry],
+        data_size: usize,
+    ) -> Self {
         assert!(!slots.is_empty());
         assert!(data_size > 0);
+        assert!(index.len() >= index_capacity(slots.len()));
 
-        // Init array
+        // Init arrays
         for slot in slots.iter_mut().skip(1) {
             *slot = MemorySlot::empty();
         }
         slots[0] = MemorySlot::with_size(data_size);
+        index.fill(IndexEntry::empty());
+
+        Self::new(slots, index)
+    }
+
+    /// Set default alignment of objects added to pool.
+    pub fn with_alignment(mut self, alignment: usize) -> Self {
+        assert!(check_alignment(alignment).is_ok());
+        self.alignment = alignment;
+        self
+    }
+
+    /// Set offset of data region relative to mapping base.
+    ///
+    /// Alignment is computed relative to mapping base and not relative to data region.
+    pub fn with_base_offset(mut self, base_offset: usize) -> Self {
+        self.base_offset = base_offset;
+        self
+    }
+
+    /// Set policy used to find free blocks.
+    pub fn with_strategy(mut self, strategy: AllocationStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Set counter used to give a generation to new objects.
+    ///
+    /// Counter must be shared by every user of pool. Without counter, objects
+    /// have no generation and handles are not checked.
+    pub fn with_generation_counter(mut self, counter: &'a AtomicU64) -> Self {
+        self.generation_counter = Some(counter);
+        self
+    }
+
+    /// Set event notified each time memory is given back to pool.
+    ///
+    /// Allocations which failed for lack of space can wait for it before retrying.
+    pub fn with_release_event(mut self, event: &'a Event) -> Self {
+        self.release_event = Some(event);
+        self
+    }
+
+    /// Get generation of next added object.
+    fn next_generation(&self) -> u64 {
+        self.generation_counter
+            .map_or(0, |counter| counter.fetch_add(1, Ordering::Relaxed))
+    }
 
-        Self::new(slots)
+    /// Get a new python ID not used by any object of pool.
+    ///
+    /// IDs are taken from `counter`, which must be shared by every user of pool,
+    /// so an ID is never issued twice. Counter wraps back to
+    /// [`FIRST_POOL_PYTHON_ID`], so issued IDs never collide with caller ones.
+    pub fn mint_python_id(&self, counter: &AtomicU64) -> PythonId {
+        loop {
+            let next = counter.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |x| {
+                Some(x.checked_add(1).unwrap_or(FIRST_POOL_PYTHON_ID))
+            });
+            let python_id = PythonId(next.unwrap_or_else(|x| x));
+            if python_id.valid().is_ok() && self.index.get(python_id).is_none() {
+                return python_id;
+            }
+        }
     }
 
-    /// Add new object to pool.
+    /// Add new object to pool using default alignment.
     pub fn add_object(
         &mut self,
         python_id: PythonId,
         request_size: usize,
+    ) -> Result<usize, ArrayPoolError> {
+        self.add_object_aligned(python_id, request_size, self.alignment)
+    }
+
+    /// Add object migrated from a previous segment format.
+    ///
+    /// Object data is not aligned. Its references are untracked, since previous
+    /// format does not record which processes hold them.
+    pub fn add_migrated_object(
+        &mut self,
+        python_id: PythonId,
+        request_size: usize,
+        refcount: usize,
+        source_pid: u32,
+        flags: u8,
+    ) -> Result<usize, ArrayPoolError> {
+        let offset = self.add_object_aligned(python_id, request_size, 1)?;
+
+        let slot = &mut self.slots[self.index_of(python_id)?];
+        slot.refcount = refcount as u64;
+        slot.holders = [Holder::empty(); MAX_HOLDERS];
+        slot.source_pid = source_pid;
+        slot.flags = flags & FLAG_MEMSLOT_TRANSFERED;
+        Ok(offset)
+    }
+
+    /// Add new object to pool with a specific data alignment.
+    pub fn add_object_aligned(
+        &mut self,
+        python_id: PythonId,
+        request_size: usize,
+        alignment: usize,
+    ) -> Result<usize, ArrayPoolError> {
+        self.add_object_with_flags(python_id, request_size, alignment, 0)
+    }
+
+    /// Add new object to pool with room for an array descriptor.
+    ///
+    /// Descriptor must be written in the [`DESCRIPTOR_SIZE`] bytes located
+    /// just before returned data offset.
+    pub fn add_described_object(
+        &mut self,
+        python_id: PythonId,
+        request_size: usize,
+        alignment: usize,
+    ) -> Result<usize, ArrayPoolError> {
+        self.add_object_with_flags(python_id, request_size, alignment, FLAG_MEMSLOT_DESCRIBED)
+    }
+
+    fn add_object_with_flags(
+        &mut self,
+        python_id: PythonId,
+        request_size: usize,
+        alignment: usize,
+        flags: u8,
     ) -> Result<usize, ArrayPoolError> {
         python_id.valid()?;
+        check_alignment(alignment)?;
 
         // Check object does not already exists
-        if self.slots.iter().any(|x| x.python_id == python_id) {
+        if self.index.get(python_id).is_some() {
             return Err(ArrayPoolError::ObjectAlreadyExists(python_id));
         }
 
         // Find free space
-        let target_idx = self
-            .slots
-            .iter()
-            .position(|slot| slot.is_free() && request_size <= slot.size)
+        let prefix_size = prefix_size_for(flags);
+        let (target_idx, offset, padding) = self
+            .find_free_slot(request_size, alignment, prefix_size)
             .ok_or(ArrayPoolError::NoSpaceLeft)?;
+        let block_size = padding + prefix_size + request_size;
+        let segment = self.slots[target_idx].segment;
 
-        if request_size < self.slots[target_idx].size {
+        if block_size < self.slots[target_idx].size() {
             let slot_len = self.slots.len();
             debug_assert!(slot_len > 0);
 
@@ -246,53 +854,81 @@ impl<'a> MemoryPool<'a> {
 
             // Fill info in newly created space
             (self.slots[target_idx], self.slots[target_idx + 1]) =
-                self.slots[target_idx].split_block(request_size);
+                self.slots[target_idx].split_block(block_size);
+            self.reindex_from(target_idx + 2);
         }
 
         // Fill memory info
-        self.slots[target_idx] = MemorySlot::with_object_id(python_id, request_size);
+        self.slots[target_idx] = MemorySlot::with_object_id(python_id, block_size)
+            .set_offset(offset)
+            .set_padding(padding)
+            .set_alignment(alignment)
+            .set_segment(segment)
+            .set_flags(flags)
+            .set_generation(self.next_generation());
+        self.index.insert(python_id, target_idx);
+
+        // Get address of newly created bloc data
+        Ok(offset + padding + prefix_size)
+    }
 
-        // Get address of newly created bloc
-        Ok(self.offset_by_index(target_idx))
+    /// Find free slot matching request using pool strategy.
+    ///
+    /// Returns slot index, slot offset and padding required to align data
+    /// stored after `prefix_size` bytes.
+    fn find_free_slot(
+        &self,
+        request_size: usize,
+        alignment: usize,
+        prefix_size: usize,
+    ) -> Option<(usize, usize, usize)> {
+        let mut best: Option<(usize, (usize, usize, usize))> = None;
+
+        for (idx, slot) in self.slots.iter().enumerate() {
+            let offset = slot.offset();
+            let padding = padding_for(self.base_offset + offset + prefix_size, alignment);
+            if slot.is_free() && padding + prefix_size + request_size <= slot.size() {
+                let key = match self.strategy {
+                    AllocationStrategy::FirstFit => return Some((idx, offset, padding)),
+                    AllocationStrategy::BestFit => slot.size(),
+                    AllocationStrategy::Segregated => size_class(slot.size()) as usize,
+                };
+
+                if !matches!(best, Some((best_key, _)) if best_key <= key) {
+                    best = Some((key, (idx, offset, padding)));
+                }
+            }
+        }
+
+        best.map(|(_, target)| target)
     }
 
     /// Increase ref count usage by 1 for a given python object.
-    pub fn attach_object(&mut self, python_id: PythonId) -> Result<ObjectInfo, ArrayPoolError> {
-        python_id.valid()?;
-
+    pub fn attach_object<H>(&mut self, handle: H) -> Result<ObjectInfo, ArrayPoolError>
+    where
+        H: Into<ObjectHandle>,
+    {
         // Get object index
-        let object_index = self
-            .slots
-            .iter()
-            .position(|slot| slot.python_id == python_id)
-            .ok_or(ArrayPoolError::ObjectNotFound(python_id))?;
+        let object_index = self.index_of_handle(handle.into())?;
 
         // Increase refcount and update internals
-        self.slots[object_index].refcount += 1;
+        self.slots[object_index].add_holder(process::id());
         self.slots[object_index].update_flags();
 
-        Ok(ObjectInfo::new(
-            self.offset_by_index(object_index),
-            self.slots[object_index].size,
-        ))
+        Ok(self.info_by_index(object_index))
     }
 
     /// Decrease ref count usage by 1 for a given python object.
     ///
     /// If reference count reach 0, object will be remove from pool.
-    pub fn detach_object(&mut self, python_id: PythonId) -> Result<(), ArrayPoolError> {
-        python_id.valid()?;
-
-        let object_index = self
-            .slots
-            .iter()
-            .position(|slot| slot.python_id == python_id)
-            .ok_or(ArrayPoolError::ObjectNotFound(python_id))?;
+    pub fn detach_object<H>(&mut self, handle: H) -> Result<(), ArrayPoolError>
+    where
+        H: Into<ObjectHandle>,
+    {
+        let object_index = self.index_of_handle(handle.into())?;
 
         // Decrease reference count and release slot if now unused.
-        if self.slots[object_index].refcount > 0 {
-            self.slots[object_index].refcount -= 1;
-        }
+        self.slots[object_index].remove_holder(process::id());
 
         if self.slots[object_index].is_releasable() {
             self.release_offset(object_index);
@@ -303,13 +939,11 @@ impl<'a> MemoryPool<'a> {
 
     /// Set python ID object as now releasable. Even if it has not been transfered
     /// between processes.
-    pub fn set_object_releasable(&mut self, python_id: PythonId) -> Result<(), ArrayPoolError> {
-        python_id.valid()?;
-        let object_index = self
-            .slots
-            .iter()
-            .position(|slot| slot.python_id == python_id)
-            .ok_or(ArrayPoolError::ObjectNotFound(python_id))?;
+    pub fn set_object_releasable<H>(&mut self, handle: H) -> Result<(), ArrayPoolError>
+    where
+        H: Into<ObjectHandle>,
+    {
+        let object_index = self.index_of_handle(handle.into())?;
 
         // Update flags and release slot if now unused.
         self.slots[object_index].set_transfered();
@@ -320,45 +954,538 @@ impl<'a> MemoryPool<'a> {
         Ok(())
     }
 
-    /// Get offset for a given object index.
-    fn offset_by_index(&self, object_index: usize) -> usize {
-        self.slots[..object_index].iter().map(|x| x.size).sum()
-    }
-
-    /// Update internal slots to mark slot as now free.
-    fn release_offset(&mut self, object_index: usize) {
-        debug_assert!(!self.slots[object_index].is_free());
-        let slot_len = self.slots.len();
-
-        // Mark bloc as now free
-        self.slots[object_index] = MemorySlot::with_size(self.slots[object_index].size);
-
-        // Merge with next bloc if free
-        if object_index + 1 < slot_len && self.slots[object_index + 1].is_free() {
-            self.slots[object_index].size += self.slots[object_index + 1].size;
-            self.slots[object_index + 1] = MemorySlot::empty();
-            self.slots[object_index + 1..slot_len].rotate_left(1);
+    /// Drop references held by dead processes and release objects now unused.
+    ///
+    /// `is_alive` is called with ID of each process holding references. Objects
+    /// created by a dead process are considered as transfered, so they are
+    /// released once nobody uses them.
+    ///
+    /// Returns number of released objects.
+    pub fn reclaim_dead_processes<F>(&mut self, mut is_alive: F) -> usize
+    where
+        F: FnMut(u32) -> bool,
+    {
+        let mut releasable = Vec::new();
+        for slot in self.slots.iter_mut().filter(|x| !x.is_free()) {
+            slot.remove_dead_holders(&mut is_alive);
+            if !is_alive(slot.source_pid) {
+                slot.set_transfered();
+            }
+            if slot.is_releasable() {
+                releasable.push(slot.python_id);
+            }
         }
 
-        // Merge with previous block if free
-        if object_index > 0 && self.slots[object_index - 1].is_free() {
-            self.slots[object_index - 1].size += self.slots[object_index].size;
-            self.slots[object_index] = MemorySlot::empty();
-            self.slots[object_index..slot_len].rotate_left(1);
+        // Slots are moved when released, so they are searched again
+        for python_id in &releasable {
+            if let Ok(object_index) = self.index_of(*python_id) {
+                self.release_offset(object_index);
+            }
         }
-    }
 
-    /// Get object info of given python object.
-    pub fn info_of(&self, python_id: PythonId) -> Option<ObjectInfo> {
-        python_id.valid().ok()?;
+        releasable.len()
+    }
 
-        let position = self.slots.iter().position(|x| x.python_id == python_id)?;
-        Some(ObjectInfo::new(
-            self.offset_by_index(position),
-            self.slots[position].size,
+    /// Remove object from pool whatever its reference count is.
+    pub fn remove_object(&mut self, python_id: PythonId) -> Result<(), ArrayPoolError> {
+        python_id.valid()?;
+        let object_index = self.index_of(python_id)?;
+        self.release_offset(object_index);
+        Ok(())
+    }
+
+    /// Change size of an existing object.
+    ///
+    /// Object is shrunk or grown in place when possible. Otherwise it is moved
+    /// to a new free block: `copy_data` is then called with current and new
+    /// location of object data (array descriptor included) and must copy it
+    /// (regions never overlap). Object cannot be moved if it may be used through
+    /// another reference than the one held by current process.
+    ///
+    /// Returns new object info.
+    pub fn resize_object<F>(
+        &mut self,
+        python_id: PythonId,
+        new_size: usize,
+        copy_data: F,
+    ) -> Result<ObjectInfo, ArrayPoolError>
+    where
+        F: FnOnce(ObjectInfo, ObjectInfo),
+    {
+        python_id.valid()?;
+        let object_index = self.index_of(python_id)?;
+
+        let slot = self.slots[object_index];
+        let prefix_size = slot.prefix_size();
+        let block_size = slot.padding() + prefix_size + new_size;
+        let slot_len = self.slots.len();
+        let next_index = object_index + 1;
+        let next_is_free = next_index < slot_len
+            && self.slots[next_index].is_free()
+            && self.slots[next_index] != MemorySlot::empty()
+            && self.slots[next_index].segment == slot.segment;
+
+        // Shrink in place and give tail back to next free block (or to a new one)
+        if block_size <= slot.size() {
+            let tail_size = slot.size() - block_size;
+            if tail_size > 0 {
+                if next_is_free {
+                    self.slots[next_index].offset -= tail_size as u64;
+                    self.slots[next_index].size += tail_size as u64;
+                } else {
+                    if self.slots[slot_len - 1] != MemorySlot::empty() {
+                        return Err(ArrayPoolError::NoFreeBlocLeft);
+                    }
+
+                    self.slots[next_index..slot_len].rotate_right(1);
+                    (self.slots[object_index], self.slots[next_index]) =
+                        slot.split_block(block_size);
+                    self.reindex_from(next_index + 1);
+                }
+                self.slots[object_index].size = block_size as u64;
+
+                if let Some(event) = self.release_event {
+                    event.notify_all();
+                }
+            }
+            return Ok(self.info_by_index(object_index));
+        }
+
+        // Grow in place using next free block
+        let missing = block_size - slot.size();
+        if next_is_free && self.slots[next_index].size() >= missing {
+            if self.slots[next_index].size() == missing {
+                self.slots[next_index] = MemorySlot::empty();
+                self.slots[next_index..slot_len].rotate_left(1);
+                self.reindex_from(next_index);
+            } else {
+                self.slots[next_index].offset += missing as u64;
+                self.slots[next_index].size -= missing as u64;
+            }
+            self.slots[object_index].size = block_size as u64;
+            return Ok(self.info_by_index(object_index));
+        }
+
+        // Move object to a new block (old block is released)
+        if slot.used_by_others(process::id()) {
+            return Err(ArrayPoolError::ObjectInUse(python_id));
+        }
+        let (target_idx, offset, padding) = self
+            .find_free_slot(new_size, slot.alignment(), prefix_size)
+            .ok_or(ArrayPoolError::NoSpaceLeft)?;
+        let block_size = padding + prefix_size + new_size;
+        let segment = self.slots[target_idx].segment;
+
+        if block_size < self.slots[target_idx].size() {
+            if self.slots[slot_len - 1] != MemorySlot::empty() {
+                return Err(ArrayPoolError::NoFreeBlocLeft);
+            }
+
+            self.slots[target_idx + 1..slot_len].rotate_right(1);
+            (self.slots[target_idx], self.slots[target_idx + 1]) =
+                self.slots[target_idx].split_block(block_size);
+            self.reindex_from(target_idx + 2);
+        }
+
+        let mut moved = slot;
+        moved.offset = offset as u64;
+        moved.size = block_size as u64;
+        moved.padding = padding as u64;
+        moved.segment = segment;
+        self.slots[target_idx] = moved;
+
+        let old_slot = self.slots[self.index_of(python_id)?];
+        copy_data(
+            ObjectInfo::new(
+                old_slot.offset() + old_slot.padding(),
+                prefix_size + old_slot.object_size().min(new_size),
+            )
+            .with_segment(old_slot.segment as usize),
+            ObjectInfo::new(offset + padding, prefix_size + new_size)
+                .with_segment(segment as usize),
+        );
+        let new_info = self.info_by_index(target_idx);
+
+        // Release old block (index still points to it) and index moved object
+        let old_index = self.index_of(python_id)?;
+        let merged = self.release_offset(old_index);
+        let moved_index = if target_idx > old_index {
+            target_idx - merged
+        } else {
+            target_idx
+        };
+        self.index.insert(python_id, moved_index);
+
+        Ok(new_info)
+    }
+
+    /// Get slot index of given python object.
+    fn index_of(&self, python_id: PythonId) -> Result<usize, ArrayPoolError> {
+        let object_index = self
+            .index
+            .get(python_id)
+            .ok_or(ArrayPoolError::ObjectNotFound(python_id))?;
+
+        debug_assert_eq!(self.slots[object_index].python_id, python_id);
+        Ok(object_index)
+    }
+
+    /// Get slot index of object referenced by handle.
+    fn index_of_handle(&self, handle: ObjectHandle) -> Result<usize, ArrayPoolError> {
+        handle.python_id.valid()?;
+        let object_index = self.index_of(handle.python_id)?;
+
+        let generation = self.slots[object_index].generation;
+        if handle.generation != 0 && handle.generation != generation {
+            return Err(ArrayPoolError::StaleHandle(handle.python_id));
+        }
+        Ok(object_index)
+    }
+
+    /// Check handle references an object of pool.
+    pub fn check_handle<H>(&self, handle: H) -> Result<(), ArrayPoolError>
+    where
+        H: Into<ObjectHandle>,
+    {
+        self.index_of_handle(handle.into())?;
+        Ok(())
+    }
+
+    /// Get object info of object referenced by handle.
+    pub fn handle_info_of<H>(&self, handle: H) -> Result<ObjectInfo, ArrayPoolError>
+    where
+        H: Into<ObjectHandle>,
+    {
+        let object_index = self.index_of_handle(handle.into())?;
+        Ok(self.info_by_index(object_index))
+    }
+
+    /// Get handle of given python object.
+    pub fn handle_of(&self, python_id: PythonId) -> Result<ObjectHandle, ArrayPoolError> {
+        python_id.valid()?;
+        let object_index = self.index_of(python_id)?;
+        Ok(ObjectHandle::new(
+            python_id,
+            self.slots[object_index].generation,
         ))
     }
 
+    /// Update index of objects whose slot has been moved, starting at `start`.
+    fn reindex_from(&mut self, start: usize) {
+        for (idx, slot) in self.slots.iter().enumerate().skip(start) {
+            if *slot == MemorySlot::empty() {
+                // Only unused slots remain
+                break;
+            }
+            if !slot.is_free() {
+                self.index.insert(slot.python_id, idx);
+            }
+        }
+    }
+
+    /// Get object info (data offset and size, padding excluded) for a given object index.
+    fn info_by_index(&self, object_index: usize) -> ObjectInfo {
+        let slot = &self.slots[object_index];
+        ObjectInfo::new(slot.data_offset(), slot.object_size()).with_segment(slot.segment as usize)
+    }
+
+    /// Update internal slots to mark slot as now free.
+    ///
+    /// Returns number of slots merged with released one (slots after it are
+    /// moved back by as many positions).
+    fn release_offset(&mut self, object_index: usize) -> usize {
+        debug_assert!(!self.slots[object_index].is_free());
+        let slot_len = self.slots.len();
+
+        // Mark bloc as now free (padding bytes are given back too)
+        self.index.remove(self.slots[object_index].python_id);
+        let segment = self.slots[object_index].segment;
+        self.slots[object_index] = MemorySlot::with_size(self.slots[object_index].size())
+            .set_offset(self.slots[object_index].offset())
+            .set_segment(segment);
+        let mut moved_from = slot_len;
+        let mut merged = 0;
+
+        // Merge with next bloc if free (blocks of different segments are not contiguous)
+        if object_index + 1 < slot_len
+            && self.slots[object_index + 1].is_free()
+            && self.slots[object_index + 1].segment == segment
+        {
+            self.slots[object_index].size += self.slots[object_index + 1].size;
+            self.slots[object_index + 1] = MemorySlot::empty();
+            self.slots[object_index + 1..slot_len].rotate_left(1);
+            moved_from = object_index + 1;
+            merged += 1;
+        }
+
+        // Merge with previous block if free
+        if object_index > 0
+            && self.slots[object_index - 1].is_free()
+            && self.slots[object_index - 1].segment == segment
+        {
+            self.slots[object_index - 1].size += self.slots[object_index].size;
+            self.slots[object_index] = MemorySlot::empty();
+            self.slots[object_index..slot_len].rotate_left(1);
+            moved_from = object_index;
+            merged += 1;
+        }
+
+        self.reindex_from(moved_from);
+
+        if let Some(event) = self.release_event {
+            event.notify_all();
+        }
+        merged
+    }
+
+    /// Get number of slots (used or not) of pool.
+    pub fn slot_count(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Remove `bytes_count` bytes from end of data region of first segment.
+    ///
+    /// Last block of first segment must be free and large enough.
+    pub fn shrink_data(&mut self, bytes_count: usize) -> Result<(), ArrayPoolError> {
+        let last_idx = self
+            .slots
+            .iter()
+            .rposition(|slot| slot.segment == 0 && *slot != MemorySlot::empty())
+            .ok_or(ArrayPoolError::NoSpaceLeft)?;
+
+        let last = &mut self.slots[last_idx];
+        if !last.is_free() || last.size() < bytes_count {
+            return Err(ArrayPoolError::NoSpaceLeft);
+        }
+
+        last.size -= bytes_count as u64;
+        if last.size() == 0 {
+            *last = MemorySlot::empty();
+            self.slots[last_idx..].rotate_left(1);
+            self.reindex_from(last_idx);
+        }
+        Ok(())
+    }
+
+    /// Add free block covering data region of a new segment.
+    pub fn add_segment(&mut self, segment: u16, data_size: usize) -> Result<(), ArrayPoolError> {
+        debug_assert!(segment > 0);
+        let slot = self
+            .slots
+            .iter_mut()
+            .find(|slot| **slot == MemorySlot::empty())
+            .ok_or(ArrayPoolError::NoFreeBlocLeft)?;
+
+        *slot = MemorySlot::with_size(data_size).set_segment(segment);
+        Ok(())
+    }
+
+    /// Rebuild object index from slots content.
+    pub fn rebuild_index(&mut self) {
+        self.index.clear();
+        self.reindex_from(0);
+    }
+
+    /// Check slots describe contiguous blocks and rebuild object index.
+    ///
+    /// Used to detect a slot table left half updated by a dead process. Object
+    /// index is derived from slots, so it is rebuilt rather than checked.
+    pub fn check_consistency(&mut self) -> Result<(), ArrayPoolError> {
+        let used_count = self
+            .slots
+            .iter()
+            .position(|x| *x == MemorySlot::empty())
+            .unwrap_or(self.slots.len());
+
+        let mut previous: Option<&MemorySlot> = None;
+        for slot in &self.slots[..used_count] {
+            let contiguous = match previous {
+                Some(prev) if prev.segment == slot.segment => {
+                    slot.offset() == prev.offset() + prev.size()
+                }
+                Some(prev) => slot.segment > prev.segment && slot.offset() == 0,
+                None => slot.offset() == 0,
+            };
+            let fits = slot.is_free() || slot.padding() + slot.prefix_size() <= slot.size();
+            if !contiguous || !fits {
+                return Err(ArrayPoolError::CorruptedSlotTable);
+            }
+            previous = Some(slot);
+        }
+
+        if self.slots[used_count..]
+            .iter()
+            .any(|x| *x != MemorySlot::empty())
+        {
+            return Err(ArrayPoolError::CorruptedSlotTable);
+        }
+
+        // Same python ID must not be used twice
+        self.rebuild_index();
+        let object_count = self.slots[..used_count]
+            .iter()
+            .filter(|x| !x.is_free())
+            .count();
+        if self.index.len() != object_count {
+            return Err(ArrayPoolError::CorruptedSlotTable);
+        }
+
+        Ok(())
+    }
+
+    /// Move unused objects toward start of data region and merge free blocks.
+    ///
+    /// Only objects with a reference count of 0 are moved, since no process
+    /// hold pointer to their data. Pinned objects (see
+    /// [`MemoryPool::pinned_info_of`]) are never moved. `move_data` is called with segment index,
+    /// source offset, destination offset and size (from start of data region)
+    /// for each moved object and must copy data accordingly (regions may overlap).
+    ///
+    /// Objects are never moved to another segment.
+    ///
+    /// Returns how many bytes have been added to largest free block.
+    pub fn compact<F>(&mut self, mut move_data: F) -> usize
+    where
+        F: FnMut(usize, usize, usize, usize),
+    {
+        let largest_free_before = self.largest_free_block();
+        let slot_len = self.slots.len();
+
+        let mut idx = 0;
+        while idx + 1 < slot_len {
+            let free = self.slots[idx];
+            let object = self.slots[idx + 1];
+            if !free.is_free()
+                || free == MemorySlot::empty()
+                || !object.is_movable()
+                || free.segment != object.segment
+            {
+                idx += 1;
+                continue;
+            }
+
+            // Place object at start of free block
+            let prefix_size = object.prefix_size();
+            let padding = padding_for(
+                self.base_offset + free.offset() + prefix_size,
+                object.alignment(),
+            );
+            // Moving object into a hole with another alignment must not use more padding
+            if padding > object.padding() {
+                idx += 1;
+                continue;
+            }
+
+            let block_size = padding + prefix_size + object.object_size();
+            move_data(
+                object.segment as usize,
+                object.offset() + object.padding(),
+                free.offset() + padding,
+                prefix_size + object.object_size(),
+            );
+
+            self.slots[idx] = object;
+            self.slots[idx].offset = free.offset;
+            self.slots[idx].size = block_size as u64;
+            self.slots[idx].padding = padding as u64;
+            self.index.insert(object.python_id, idx);
+
+            // Free block is now after object
+            let remaining = free.size() + object.size() - block_size;
+            self.slots[idx + 1] = MemorySlot::with_size(remaining)
+                .set_offset(free.offset() + block_size)
+                .set_segment(free.segment);
+
+            if remaining == 0 {
+                self.slots[idx + 1] = MemorySlot::empty();
+                self.slots[idx + 1..slot_len].rotate_left(1);
+                self.reindex_from(idx + 1);
+            } else if idx + 2 < slot_len
+                && self.slots[idx + 2].is_free()
+                && self.slots[idx + 2].segment == free.segment
+            {
+                self.slots[idx + 1].size += self.slots[idx + 2].size;
+                self.slots[idx + 2] = MemorySlot::empty();
+                self.slots[idx + 2..slot_len].rotate_left(1);
+                self.reindex_from(idx + 2);
+            }
+
+            idx += 1;
+        }
+
+        self.largest_free_block()
+            .saturating_sub(largest_free_before)
+    }
+
+    /// Get size of largest free block.
+    pub fn largest_free_block(&self) -> usize {
+        self.slots
+            .iter()
+            .filter(|slot| slot.is_free())
+            .map(|slot| slot.size())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Compute pool usage statistics.
+    pub fn stats(&self) -> PoolStats {
+        let mut stats = PoolStats {
+            slot_count: self.slots.len(),
+            ..Default::default()
+        };
+
+        for slot in self.slots.iter().filter(|x| **x != MemorySlot::empty()) {
+            stats.slots_used += 1;
+            if slot.is_free() {
+                stats.free_bytes += slot.size();
+                stats.free_block_count += 1;
+                stats.largest_free_block = stats.largest_free_block.max(slot.size());
+            } else {
+                stats.used_bytes += slot.size();
+                stats.object_count += 1;
+            }
+        }
+
+        stats.total_bytes = stats.used_bytes + stats.free_bytes;
+        stats
+    }
+
+    /// Get location of array descriptor of given python object.
+    ///
+    /// Returns `None` if object has been added without descriptor.
+    pub fn descriptor_info_of(
+        &self,
+        python_id: PythonId,
+    ) -> Result<Option<ObjectInfo>, ArrayPoolError> {
+        python_id.valid()?;
+        let slot = &self.slots[self.index_of(python_id)?];
+
+        Ok((slot.prefix_size() == DESCRIPTOR_SIZE).then(|| {
+            ObjectInfo::new(slot.offset() + slot.padding(), DESCRIPTOR_SIZE)
+                .with_segment(slot.segment as usize)
+        }))
+    }
+
+    /// Get object info of given python object.
+    pub fn info_of(&self, python_id: PythonId) -> Option<ObjectInfo> {
+        python_id.valid().ok()?;
+
+        let position = self.index_of(python_id).ok()?;
+        Some(self.info_by_index(position))
+    }
+
+    /// Get object info of given python object and keep its data in place.
+    ///
+    /// Object is never moved by compaction afterwards, so data can be accessed
+    /// without holding a reference.
+    pub fn pinned_info_of(&mut self, python_id: PythonId) -> Option<ObjectInfo> {
+        python_id.valid().ok()?;
+
+        let position = self.index_of(python_id).ok()?;
+        self.slots[position].set_pinned();
+        Some(self.info_by_index(position))
+    }
+
     /// Dump memory content as a string.
     pub fn dump(&self) -> String {
         self.slots
@@ -417,6 +1544,36 @@ mod tests {
             assert!(slot.is_releasable());
         }
 
+        #[test]
+        fn test_holders() {
+            let pid = process::id();
+            let mut slot = MemorySlot::with_object_id(PythonId(12), 150);
+
+            slot.add_holder(pid);
+            for other_pid in 1..=MAX_HOLDERS as u32 {
+                slot.add_holder(other_pid);
+            }
+            assert_eq!(slot.refcount, MAX_HOLDERS as u64 + 2);
+            assert_eq!(slot.holders[0], Holder { pid, count: 2 });
+
+            // Last process does not fit in holder list
+            assert!(slot.holders.iter().all(|x| x.pid != MAX_HOLDERS as u32));
+            assert_eq!(slot.untracked_refcount(), 1);
+            slot.remove_holder(MAX_HOLDERS as u32);
+            assert_eq!(slot.refcount, MAX_HOLDERS as u64 + 1);
+
+            // References of other processes are kept once untracked ones are dropped
+            slot.remove_holder(MAX_HOLDERS as u32);
+            assert_eq!(slot.refcount, MAX_HOLDERS as u64 + 1);
+
+            slot.remove_holder(1);
+            assert_eq!(slot.holders[1], Holder::empty());
+
+            // Only references from tracked processes can be removed
+            assert_eq!(slot.remove_dead_holders(|x| x == pid), MAX_HOLDERS - 2);
+            assert_eq!(slot.refcount, 2);
+        }
+
         #[test]
         fn test_split_block() {
             let python_id = PythonId(42);
@@ -431,17 +1588,29 @@ mod tests {
                 (
                     MemorySlot {
                         python_id,
+                        offset: 0,
                         size: 50,
-                        refcount,
+                        padding: 0,
+                        refcount: refcount as u64,
                         source_pid: std::process::id(),
                         flags: FLAG_MEMSLOT_TRANSFERED,
+                        alignment_log2: 0,
+                        segment: 0,
+                        generation: 0,
+                        holders: slot.holders,
                     },
                     MemorySlot {
                         python_id: PythonId::empty(),
+                        offset: 50,
                         size: 150,
+                        padding: 0,
                         refcount: 0,
                         source_pid: 0,
                         flags: 0,
+                        alignment_log2: 0,
+                        segment: 0,
+                        generation: 0,
+                        holders: [Holder::empty(); MAX_HOLDERS],
                     },
                 )
             );
@@ -450,17 +1619,29 @@ mod tests {
                 (
                     MemorySlot {
                         python_id,
+                        offset: 0,
                         size: 0,
-                        refcount,
+                        padding: 0,
+                        refcount: refcount as u64,
                         source_pid: std::process::id(),
                         flags: FLAG_MEMSLOT_TRANSFERED,
+                        alignment_log2: 0,
+                        segment: 0,
+                        generation: 0,
+                        holders: slot.holders,
                     },
                     MemorySlot {
                         python_id: PythonId::empty(),
+                        offset: 0,
                         size: 200,
+                        padding: 0,
                         refcount: 0,
                         source_pid: 0,
                         flags: 0,
+                        alignment_log2: 0,
+                        segment: 0,
+                        generation: 0,
+                        holders: [Holder::empty(); MAX_HOLDERS],
                     },
                 )
             );
@@ -469,17 +1650,29 @@ mod tests {
                 (
                     MemorySlot {
                         python_id,
+                        offset: 0,
                         size: 199,
-                        refcount,
+                        padding: 0,
+                        refcount: refcount as u64,
                         source_pid: std::process::id(),
                         flags: FLAG_MEMSLOT_TRANSFERED,
+                        alignment_log2: 0,
+                        segment: 0,
+                        generation: 0,
+                        holders: slot.holders,
                     },
                     MemorySlot {
                         python_id: PythonId::empty(),
+                        offset: 199,
                         size: 1,
+                        padding: 0,
                         refcount: 0,
                         source_pid: 0,
                         flags: 0,
+                        alignment_log2: 0,
+                        segment: 0,
+                        generation: 0,
+                        holders: [Holder::empty(); MAX_HOLDERS],
                     },
                 )
             );
@@ -490,10 +1683,16 @@ mod tests {
         fn test_split_block_fail() {
             let slot = MemorySlot {
                 python_id: PythonId(42),
+                offset: 0,
                 size: 200,
+                padding: 0,
                 refcount: 3,
                 source_pid: std::process::id(),
                 flags: 0,
+                alignment_log2: 0,
+                segment: 0,
+                generation: 0,
+                holders: [Holder::empty(); MAX_HOLDERS],
             };
 
             let (_, _) = slot.split_block(200);
@@ -509,7 +1708,8 @@ mod tests {
         #[test]
         fn test_init() {
             let mut slots = vec![MemorySlot::empty(); SLOT_COUNT];
-            let memory = MemoryPool::from_uninit_slice(&mut slots, MEMORY_SIZE);
+            let mut index = vec![IndexEntry::empty(); index_capacity(SLOT_COUNT)];
+            let memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, MEMORY_SIZE);
             assert_eq!(
                 memory.slots,
                 vec![
@@ -524,7 +1724,8 @@ mod tests {
         #[test]
         fn test_add_invalid_python_id() {
             let mut slots = vec![MemorySlot::empty(); SLOT_COUNT];
-            let mut memory = MemoryPool::from_uninit_slice(&mut slots, MEMORY_SIZE);
+            let mut index = vec![IndexEntry::empty(); index_capacity(SLOT_COUNT)];
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, MEMORY_SIZE);
             assert_eq!(
                 memory.add_object(PythonId::empty(), 10),
                 Err(ArrayPoolError::InvalidPythonId)
@@ -534,7 +1735,8 @@ mod tests {
         #[test]
         fn test_add_duplicated_object() {
             let mut slots = vec![MemorySlot::empty(); SLOT_COUNT];
-            let mut memory = MemoryPool::from_uninit_slice(&mut slots, MEMORY_SIZE);
+            let mut index = vec![IndexEntry::empty(); index_capacity(SLOT_COUNT)];
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, MEMORY_SIZE);
 
             assert_eq!(memory.add_object(PythonId(40), 10), Ok(0));
             assert_eq!(
@@ -546,7 +1748,8 @@ mod tests {
         #[test]
         fn test_add_huge_bloc() {
             let mut slots = vec![MemorySlot::empty(); SLOT_COUNT];
-            let mut memory = MemoryPool::from_uninit_slice(&mut slots, MEMORY_SIZE);
+            let mut index = vec![IndexEntry::empty(); index_capacity(SLOT_COUNT)];
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, MEMORY_SIZE);
 
             assert_eq!(
                 memory.add_object(PythonId(40), MEMORY_SIZE + 1),
@@ -557,7 +1760,8 @@ mod tests {
         #[test]
         fn test_add_bloc() {
             let mut slots = vec![MemorySlot::empty(); SLOT_COUNT];
-            let mut memory = MemoryPool::from_uninit_slice(&mut slots, MEMORY_SIZE);
+            let mut index = vec![IndexEntry::empty(); index_capacity(SLOT_COUNT)];
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, MEMORY_SIZE);
 
             assert_eq!(memory.add_object(PythonId(40), 150), Ok(0));
             assert_eq!(memory.add_object(PythonId(41), 50), Ok(150));
@@ -567,9 +1771,10 @@ mod tests {
                 memory.slots,
                 vec![
                     MemorySlot::with_object_id(PythonId(40), 150),
-                    MemorySlot::with_object_id(PythonId(41), 50),
-                    MemorySlot::with_object_id(PythonId(42), 5 * 1024),
-                    MemorySlot::with_size(MEMORY_SIZE - 150 - 50 - (5 * 1024)),
+                    MemorySlot::with_object_id(PythonId(41), 50).set_offset(150),
+                    MemorySlot::with_object_id(PythonId(42), 5 * 1024).set_offset(200),
+                    MemorySlot::with_size(MEMORY_SIZE - 150 - 50 - (5 * 1024))
+                        .set_offset(200 + 5 * 1024),
                 ]
             );
         }
@@ -577,7 +1782,8 @@ mod tests {
         #[test]
         fn test_add_single_huge_bloc() {
             let mut slots = vec![MemorySlot::empty(); SLOT_COUNT];
-            let mut memory = MemoryPool::from_uninit_slice(&mut slots, MEMORY_SIZE);
+            let mut index = vec![IndexEntry::empty(); index_capacity(SLOT_COUNT)];
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, MEMORY_SIZE);
 
             // Add bloc
             assert_eq!(memory.add_object(PythonId(42), MEMORY_SIZE), Ok(0));
@@ -603,7 +1809,8 @@ mod tests {
         #[test]
         fn test_add_empty_bloc() {
             let mut slots = vec![MemorySlot::empty(); SLOT_COUNT];
-            let mut memory = MemoryPool::from_uninit_slice(&mut slots, MEMORY_SIZE);
+            let mut index = vec![IndexEntry::empty(); index_capacity(SLOT_COUNT)];
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, MEMORY_SIZE);
 
             // Add blocs
             assert_eq!(memory.add_object(PythonId(40), 0), Ok(0));
@@ -644,10 +1851,195 @@ mod tests {
             );
         }
 
+        #[test]
+        fn test_add_invalid_alignment() {
+            let mut slots = vec![MemorySlot::empty(); SLOT_COUNT];
+            let mut index = vec![IndexEntry::empty(); index_capacity(SLOT_COUNT)];
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, MEMORY_SIZE);
+
+            assert_eq!(
+                memory.add_object_aligned(PythonId(40), 10, 0),
+                Err(ArrayPoolError::InvalidAlignment(0))
+            );
+            assert_eq!(
+                memory.add_object_aligned(PythonId(40), 10, 24),
+                Err(ArrayPoolError::InvalidAlignment(24))
+            );
+        }
+
+        #[test]
+        fn test_add_aligned_bloc() {
+            let mut slots = vec![MemorySlot::empty(); SLOT_COUNT];
+            let mut index = vec![IndexEntry::empty(); index_capacity(SLOT_COUNT)];
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, MEMORY_SIZE)
+                .with_alignment(64);
+
+            assert_eq!(memory.add_object(PythonId(40), 3), Ok(0));
+            assert_eq!(memory.add_object(PythonId(41), 80), Ok(64));
+            assert_eq!(memory.add_object_aligned(PythonId(42), 8, 256), Ok(256));
+
+            assert_eq!(memory.info_of(PythonId(40)), Some(ObjectInfo::new(0, 3)));
+            assert_eq!(memory.info_of(PythonId(41)), Some(ObjectInfo::new(64, 80)));
+            assert_eq!(memory.info_of(PythonId(42)), Some(ObjectInfo::new(256, 8)));
+            assert_eq!(
+                memory.slots,
+                vec![
+                    MemorySlot::with_object_id(PythonId(40), 3).set_alignment(64),
+                    MemorySlot::with_object_id(PythonId(41), 61 + 80)
+                        .set_offset(3)
+                        .set_padding(61)
+                        .set_alignment(64),
+                    MemorySlot::with_object_id(PythonId(42), 112 + 8)
+                        .set_offset(144)
+                        .set_padding(112)
+                        .set_alignment(256),
+                    MemorySlot::with_size(MEMORY_SIZE - 264).set_offset(264),
+                ]
+            );
+
+            // Release padded blocs and check everything is merged back
+            assert_eq!(memory.set_object_releasable(PythonId(41)), Ok(()));
+            assert_eq!(memory.detach_object(PythonId(41)), Ok(()));
+            assert_eq!(memory.set_object_releasable(PythonId(42)), Ok(()));
+            assert_eq!(memory.detach_object(PythonId(42)), Ok(()));
+            assert_eq!(
+                memory.slots,
+                vec![
+                    MemorySlot::with_object_id(PythonId(40), 3).set_alignment(64),
+                    MemorySlot::with_size(MEMORY_SIZE - 3).set_offset(3),
+                    MemorySlot::empty(),
+                    MemorySlot::empty(),
+                ]
+            );
+        }
+
+        #[test]
+        fn test_add_aligned_with_base_offset() {
+            let mut slots = vec![MemorySlot::empty(); SLOT_COUNT];
+            let mut index = vec![IndexEntry::empty(); index_capacity(SLOT_COUNT)];
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, MEMORY_SIZE)
+                .with_alignment(64)
+                .with_base_offset(40);
+
+            // Data region starts at 40 bytes from mapping base
+            assert_eq!(memory.add_object(PythonId(40), 10), Ok(24));
+            assert_eq!(memory.add_object(PythonId(41), 10), Ok(88));
+        }
+
+        #[test]
+        fn test_add_migrated_object() {
+            let mut slots = vec![MemorySlot::empty(); SLOT_COUNT];
+            let mut index = vec![IndexEntry::empty(); index_capacity(SLOT_COUNT)];
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, MEMORY_SIZE)
+                .with_alignment(64);
+
+            // Objects are not aligned and keep their state
+            assert_eq!(
+                memory.add_migrated_object(PythonId(40), 10, 2, 1, FLAG_MEMSLOT_TRANSFERED),
+                Ok(0)
+            );
+            assert_eq!(
+                memory.add_migrated_object(PythonId(41), 10, 0, 1, 0),
+                Ok(10)
+            );
+            assert_eq!(memory.slots[0].refcount, 2);
+            assert_eq!(memory.slots[0].untracked_refcount(), 2);
+            assert_eq!(memory.slots[0].source_pid, 1);
+            assert!(memory.slots[1].is_movable());
+
+            // Untracked references are dropped by any process
+            assert_eq!(memory.detach_object(PythonId(40)), Ok(()));
+            assert_eq!(memory.detach_object(PythonId(40)), Ok(()));
+            assert_eq!(memory.info_of(PythonId(40)), None);
+        }
+
+        #[test]
+        fn test_add_aligned_does_not_fit_padding() {
+            let mut slots = vec![MemorySlot::empty(); SLOT_COUNT];
+            let mut index = vec![IndexEntry::empty(); index_capacity(SLOT_COUNT)];
+            let mut memory =
+                MemoryPool::from_uninit_slice(&mut slots, &mut index, 128).with_alignment(64);
+
+            assert_eq!(memory.add_object(PythonId(40), 1), Ok(0));
+            assert_eq!(
+                memory.add_object(PythonId(41), 65),
+                Err(ArrayPoolError::NoSpaceLeft)
+            );
+            assert_eq!(memory.add_object(PythonId(41), 64), Ok(64));
+        }
+
+        /// Create pool with holes of 100, 30 and 60 bytes followed by free tail.
+        fn fill_with_holes(memory: &mut MemoryPool) {
+            for (python_id, size) in [(40, 100), (41, 10), (42, 30), (43, 10), (44, 60), (45, 10)] {
+                assert!(memory.add_object(PythonId(python_id), size).is_ok());
+            }
+            for python_id in [40, 42, 44] {
+                assert_eq!(memory.set_object_releasable(PythonId(python_id)), Ok(()));
+                assert_eq!(memory.detach_object(PythonId(python_id)), Ok(()));
+            }
+        }
+
+        #[test]
+        fn test_first_fit_strategy() {
+            let mut slots = vec![MemorySlot::empty(); 12];
+            let mut index = vec![IndexEntry::empty(); index_capacity(12)];
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, MEMORY_SIZE)
+                .with_strategy(AllocationStrategy::FirstFit);
+            fill_with_holes(&mut memory);
+
+            assert_eq!(memory.add_object(PythonId(50), 25), Ok(0));
+            assert_eq!(memory.add_object(PythonId(51), 50), Ok(25));
+            assert_eq!(memory.add_object(PythonId(52), 200), Ok(220));
+        }
+
+        #[test]
+        fn test_best_fit_strategy() {
+            let mut slots = vec![MemorySlot::empty(); 12];
+            let mut index = vec![IndexEntry::empty(); index_capacity(12)];
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, MEMORY_SIZE)
+                .with_strategy(AllocationStrategy::BestFit);
+            fill_with_holes(&mut memory);
+
+            assert_eq!(memory.add_object(PythonId(50), 25), Ok(110));
+            assert_eq!(memory.add_object(PythonId(51), 50), Ok(150));
+            assert_eq!(memory.add_object(PythonId(52), 70), Ok(0));
+            assert_eq!(memory.add_object(PythonId(53), 200), Ok(220));
+        }
+
+        #[test]
+        fn test_segregated_strategy() {
+            let mut slots = vec![MemorySlot::empty(); 12];
+            let mut index = vec![IndexEntry::empty(); index_capacity(12)];
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, MEMORY_SIZE)
+                .with_strategy(AllocationStrategy::Segregated);
+            fill_with_holes(&mut memory);
+
+            // 30 bytes hole is in class [16, 32), 60 bytes hole in class [32, 64)
+            assert_eq!(memory.add_object(PythonId(50), 20), Ok(110));
+            assert_eq!(memory.add_object(PythonId(51), 40), Ok(150));
+            assert_eq!(memory.add_object(PythonId(52), 200), Ok(220));
+        }
+
+        #[test]
+        fn test_strategy_raw_value() {
+            for strategy in [
+                AllocationStrategy::FirstFit,
+                AllocationStrategy::BestFit,
+                AllocationStrategy::Segregated,
+            ] {
+                assert_eq!(
+                    AllocationStrategy::from_raw(strategy.as_raw()),
+                    Some(strategy)
+                );
+            }
+            assert_eq!(AllocationStrategy::from_raw(42), None);
+        }
+
         #[test]
         fn test_detach_invalid_python_id() {
             let mut slots = vec![MemorySlot::empty(); SLOT_COUNT];
-            let mut memory = MemoryPool::from_uninit_slice(&mut slots, MEMORY_SIZE);
+            let mut index = vec![IndexEntry::empty(); index_capacity(SLOT_COUNT)];
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, MEMORY_SIZE);
             assert_eq!(
                 memory.detach_object(PythonId::empty()),
                 Err(ArrayPoolError::InvalidPythonId)
@@ -657,7 +2049,8 @@ mod tests {
         #[test]
         fn test_detach_not_found() {
             let mut slots = vec![MemorySlot::empty(); SLOT_COUNT];
-            let mut memory = MemoryPool::from_uninit_slice(&mut slots, MEMORY_SIZE);
+            let mut index = vec![IndexEntry::empty(); index_capacity(SLOT_COUNT)];
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, MEMORY_SIZE);
 
             assert_eq!(
                 memory.detach_object(PythonId(42)),
@@ -668,7 +2061,8 @@ mod tests {
         #[test]
         fn test_add_and_detach() {
             let mut slots = vec![MemorySlot::empty(); SLOT_COUNT];
-            let mut memory = MemoryPool::from_uninit_slice(&mut slots, MEMORY_SIZE);
+            let mut index = vec![IndexEntry::empty(); index_capacity(SLOT_COUNT)];
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, MEMORY_SIZE);
 
             // Add
             assert!(memory.add_object(PythonId(40), 10).is_ok());
@@ -676,7 +2070,7 @@ mod tests {
                 memory.slots,
                 vec![
                     MemorySlot::with_object_id(PythonId(40), 10),
-                    MemorySlot::with_size(MEMORY_SIZE - 10),
+                    MemorySlot::with_size(MEMORY_SIZE - 10).set_offset(10),
                     MemorySlot::empty(),
                     MemorySlot::empty(),
                 ]
@@ -699,7 +2093,8 @@ mod tests {
         #[test]
         fn test_detach_elements() {
             let mut slots = vec![MemorySlot::empty(); SLOT_COUNT];
-            let mut memory = MemoryPool::from_uninit_slice(&mut slots, MEMORY_SIZE);
+            let mut index = vec![IndexEntry::empty(); index_capacity(SLOT_COUNT)];
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, MEMORY_SIZE);
 
             // Add
             assert!(memory.add_object(PythonId(40), 10).is_ok());
@@ -715,9 +2110,9 @@ mod tests {
                 memory.slots,
                 vec![
                     MemorySlot::with_object_id(PythonId(40), 10),
-                    MemorySlot::with_size(10),
-                    MemorySlot::with_object_id(PythonId(42), 10),
-                    MemorySlot::with_size(MEMORY_SIZE - 10 - 10 - 10),
+                    MemorySlot::with_size(10).set_offset(10),
+                    MemorySlot::with_object_id(PythonId(42), 10).set_offset(20),
+                    MemorySlot::with_size(MEMORY_SIZE - 10 - 10 - 10).set_offset(30),
                 ]
             );
 
@@ -729,8 +2124,8 @@ mod tests {
                 memory.slots,
                 vec![
                     MemorySlot::with_size(20),
-                    MemorySlot::with_object_id(PythonId(42), 10),
-                    MemorySlot::with_size(MEMORY_SIZE - 10 - 10 - 10),
+                    MemorySlot::with_object_id(PythonId(42), 10).set_offset(20),
+                    MemorySlot::with_size(MEMORY_SIZE - 10 - 10 - 10).set_offset(30),
                     MemorySlot::empty(),
                 ]
             );
@@ -752,7 +2147,8 @@ mod tests {
         #[test]
         fn test_fill_holes() {
             let mut slots = vec![MemorySlot::empty(); SLOT_COUNT];
-            let mut memory = MemoryPool::from_uninit_slice(&mut slots, MEMORY_SIZE);
+            let mut index = vec![IndexEntry::empty(); index_capacity(SLOT_COUNT)];
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, MEMORY_SIZE);
 
             // Add
             assert!(memory.add_object(PythonId(40), 10).is_ok());
@@ -768,8 +2164,8 @@ mod tests {
                 memory.slots,
                 vec![
                     MemorySlot::with_size(20),
-                    MemorySlot::with_object_id(PythonId(42), 10),
-                    MemorySlot::with_size(MEMORY_SIZE - 10 - 10 - 10),
+                    MemorySlot::with_object_id(PythonId(42), 10).set_offset(20),
+                    MemorySlot::with_size(MEMORY_SIZE - 10 - 10 - 10).set_offset(30),
                     MemorySlot::empty(),
                 ]
             );
@@ -780,9 +2176,9 @@ mod tests {
                 memory.slots,
                 vec![
                     MemorySlot::with_object_id(PythonId(43), 15),
-                    MemorySlot::with_size(5),
-                    MemorySlot::with_object_id(PythonId(42), 10),
-                    MemorySlot::with_size(MEMORY_SIZE - 10 - 10 - 10),
+                    MemorySlot::with_size(5).set_offset(15),
+                    MemorySlot::with_object_id(PythonId(42), 10).set_offset(20),
+                    MemorySlot::with_size(MEMORY_SIZE - 10 - 10 - 10).set_offset(30),
                 ]
             );
 
@@ -791,31 +2187,602 @@ mod tests {
                 memory.slots,
                 vec![
                     MemorySlot::with_object_id(PythonId(43), 15),
-                    MemorySlot::with_object_id(PythonId(44), 5),
-                    MemorySlot::with_object_id(PythonId(42), 10),
-                    MemorySlot::with_size(MEMORY_SIZE - 10 - 10 - 10),
+                    MemorySlot::with_object_id(PythonId(44), 5).set_offset(15),
+                    MemorySlot::with_object_id(PythonId(42), 10).set_offset(20),
+                    MemorySlot::with_size(MEMORY_SIZE - 10 - 10 - 10).set_offset(30),
                 ]
             );
         }
 
+        #[test]
+        fn test_offsets_consistency() {
+            let mut slots = vec![MemorySlot::empty(); 32];
+            let mut index = vec![IndexEntry::empty(); index_capacity(32)];
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, MEMORY_SIZE)
+                .with_alignment(8);
+
+            for python_id in 1..20 {
+                assert!(memory
+                    .add_object(PythonId(python_id), python_id as usize * 3)
+                    .is_ok());
+            }
+            for python_id in [3, 4, 10, 12, 11, 1, 19, 18] {
+                assert_eq!(memory.set_object_releasable(PythonId(python_id)), Ok(()));
+                assert_eq!(memory.detach_object(PythonId(python_id)), Ok(()));
+            }
+            for python_id in 20..25 {
+                assert!(memory.add_object(PythonId(python_id), 7).is_ok());
+            }
+
+            // Check stored offsets match blocks sizes
+            let mut offset = 0;
+            for slot in memory
+                .slots
+                .iter()
+                .take_while(|x| **x != MemorySlot::empty())
+            {
+                assert_eq!(slot.offset(), offset);
+                offset += slot.size();
+            }
+            assert_eq!(offset, MEMORY_SIZE);
+        }
+
+        #[test]
+        fn test_compact() {
+            let mut slots = vec![MemorySlot::empty(); 8];
+            let mut index = vec![IndexEntry::empty(); index_capacity(8)];
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, 100);
+
+            // Create layout: free(10) | 41 (unused) | free(10) | 43 (used) | free(10) | 45 (unused) | free(40)
+            for python_id in 40..46 {
+                assert!(memory.add_object(PythonId(python_id), 10).is_ok());
+            }
+            for python_id in [40, 42, 44] {
+                assert_eq!(memory.set_object_releasable(PythonId(python_id)), Ok(()));
+                assert_eq!(memory.detach_object(PythonId(python_id)), Ok(()));
+            }
+            assert_eq!(memory.detach_object(PythonId(41)), Ok(()));
+            assert_eq!(memory.detach_object(PythonId(45)), Ok(()));
+            assert_eq!(memory.largest_free_block(), 40);
+
+            let mut moves = Vec::new();
+            assert_eq!(
+                memory.compact(|_segment, from, to, size| moves.push((from, to, size))),
+                10
+            );
+            assert_eq!(moves, vec![(10, 0, 10), (50, 40, 10)]);
+
+            assert_eq!(memory.info_of(PythonId(41)), Some(ObjectInfo::new(0, 10)));
+            assert_eq!(memory.info_of(PythonId(43)), Some(ObjectInfo::new(30, 10)));
+            assert_eq!(memory.info_of(PythonId(45)), Some(ObjectInfo::new(40, 10)));
+            assert_eq!(
+                memory.slots,
+                vec![
+                    MemorySlot::with_object_id(PythonId(41), 10).set_refcount(0),
+                    MemorySlot::with_size(20).set_offset(10),
+                    MemorySlot::with_object_id(PythonId(43), 10).set_offset(30),
+                    MemorySlot::with_object_id(PythonId(45), 10)
+                        .set_offset(40)
+                        .set_refcount(0),
+                    MemorySlot::with_size(50).set_offset(50),
+                    MemorySlot::empty(),
+                    MemorySlot::empty(),
+                    MemorySlot::empty(),
+                ]
+            );
+        }
+
+        #[test]
+        fn test_compact_keeps_alignment() {
+            let mut slots = vec![MemorySlot::empty(); 8];
+            let mut index = vec![IndexEntry::empty(); index_capacity(8)];
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, 1024);
+
+            assert!(memory.add_object(PythonId(40), 10).is_ok());
+            assert_eq!(memory.add_object_aligned(PythonId(41), 10, 64), Ok(64));
+            assert_eq!(memory.add_object_aligned(PythonId(42), 10, 16), Ok(80));
+            assert_eq!(memory.set_object_releasable(PythonId(40)), Ok(()));
+            assert_eq!(memory.detach_object(PythonId(40)), Ok(()));
+            assert_eq!(memory.detach_object(PythonId(41)), Ok(()));
+            assert_eq!(memory.detach_object(PythonId(42)), Ok(()));
+
+            let mut moves = Vec::new();
+            assert_eq!(
+                memory.compact(|_segment, from, to, size| moves.push((from, to, size))),
+                64
+            );
+            assert_eq!(moves, vec![(64, 0, 10), (80, 16, 10)]);
+            assert_eq!(memory.info_of(PythonId(41)), Some(ObjectInfo::new(0, 10)));
+            assert_eq!(memory.info_of(PythonId(42)), Some(ObjectInfo::new(16, 10)));
+        }
+
+        #[test]
+        fn test_compact_misaligned_hole() {
+            let mut slots = vec![MemorySlot::empty(); 8];
+            let mut index = vec![IndexEntry::empty(); index_capacity(8)];
+            let mut memory =
+                MemoryPool::from_uninit_slice(&mut slots, &mut index, 128).with_alignment(1);
+
+            assert_eq!(memory.add_object(PythonId(40), 1), Ok(0));
+            assert_eq!(memory.add_object(PythonId(41), 63), Ok(1));
+            assert_eq!(memory.add_object_aligned(PythonId(42), 10, 64), Ok(64));
+            assert_eq!(memory.add_object(PythonId(43), 54), Ok(74));
+            assert_eq!(memory.set_object_releasable(PythonId(41)), Ok(()));
+            for python_id in 40..44 {
+                assert_eq!(memory.detach_object(PythonId(python_id)), Ok(()));
+            }
+
+            // Hole starts 63 bytes before alignment of next object
+            let mut moves = Vec::new();
+            assert_eq!(
+                memory.compact(|_segment, from, to, size| moves.push((from, to, size))),
+                0
+            );
+            assert!(moves.is_empty());
+            assert_eq!(memory.largest_free_block(), 63);
+            assert_eq!(memory.info_of(PythonId(42)), Some(ObjectInfo::new(64, 10)));
+        }
+
+        #[test]
+        fn test_compact_skips_pinned() {
+            let mut slots = vec![MemorySlot::empty(); 8];
+            let mut index = vec![IndexEntry::empty(); index_capacity(8)];
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, 100);
+
+            for python_id in 40..43 {
+                assert!(memory.add_object(PythonId(python_id), 10).is_ok());
+            }
+            assert_eq!(memory.set_object_releasable(PythonId(40)), Ok(()));
+            assert_eq!(memory.detach_object(PythonId(40)), Ok(()));
+            assert_eq!(memory.detach_object(PythonId(41)), Ok(()));
+            assert_eq!(memory.detach_object(PythonId(42)), Ok(()));
+            assert_eq!(
+                memory.pinned_info_of(PythonId(41)),
+                Some(ObjectInfo::new(10, 10))
+            );
+
+            let mut moves = Vec::new();
+            assert_eq!(
+                memory.compact(|_segment, from, to, size| moves.push((from, to, size))),
+                0
+            );
+            assert!(moves.is_empty());
+            assert_eq!(memory.info_of(PythonId(41)), Some(ObjectInfo::new(10, 10)));
+            assert_eq!(memory.info_of(PythonId(42)), Some(ObjectInfo::new(20, 10)));
+        }
+
+        #[test]
+        fn test_shrink_data() {
+            let mut slots = vec![MemorySlot::empty(); SLOT_COUNT];
+            let mut index = vec![IndexEntry::empty(); index_capacity(SLOT_COUNT)];
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, 100);
+
+            assert_eq!(memory.shrink_data(10), Ok(()));
+            assert_eq!(memory.add_object(PythonId(40), 20), Ok(0));
+            assert_eq!(memory.shrink_data(71), Err(ArrayPoolError::NoSpaceLeft));
+            assert_eq!(memory.shrink_data(70), Ok(()));
+            assert_eq!(memory.shrink_data(1), Err(ArrayPoolError::NoSpaceLeft));
+            assert_eq!(
+                memory.slots,
+                vec![
+                    MemorySlot::with_object_id(PythonId(40), 20),
+                    MemorySlot::empty(),
+                    MemorySlot::empty(),
+                    MemorySlot::empty(),
+                ]
+            );
+        }
+
+        #[test]
+        fn test_check_consistency() {
+            let mut slots = vec![MemorySlot::empty(); SLOT_COUNT];
+            let mut index = vec![IndexEntry::empty(); index_capacity(SLOT_COUNT)];
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, MEMORY_SIZE);
+
+            assert!(memory.add_object(PythonId(40), 10).is_ok());
+            assert!(memory.add_object(PythonId(41), 10).is_ok());
+            assert_eq!(memory.check_consistency(), Ok(()));
+
+            // Duplicated object
+            memory.slots[1].python_id = PythonId(40);
+            assert_eq!(
+                memory.check_consistency(),
+                Err(ArrayPoolError::CorruptedSlotTable)
+            );
+            memory.slots[1].python_id = PythonId(41);
+
+            // Overlapping blocks
+            memory.slots[1].offset = 5;
+            assert_eq!(
+                memory.check_consistency(),
+                Err(ArrayPoolError::CorruptedSlotTable)
+            );
+            memory.slots[1].offset = 10;
+
+            // Used slot after unused ones
+            memory.slots[3] = MemorySlot::with_size(10);
+            assert_eq!(
+                memory.check_consistency(),
+                Err(ArrayPoolError::CorruptedSlotTable)
+            );
+        }
+
+        #[test]
+        fn test_rebuild_index() {
+            let mut slots = vec![MemorySlot::empty(); SLOT_COUNT];
+            let mut index = vec![IndexEntry::empty(); index_capacity(SLOT_COUNT)];
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, 100);
+
+            assert_eq!(memory.add_object(PythonId(40), 20), Ok(0));
+            assert_eq!(memory.add_object(PythonId(41), 20), Ok(20));
+
+            memory.index.clear();
+            assert_eq!(memory.info_of(PythonId(41)), None);
+
+            memory.rebuild_index();
+            assert_eq!(memory.info_of(PythonId(40)), Some(ObjectInfo::new(0, 20)));
+            assert_eq!(memory.info_of(PythonId(41)), Some(ObjectInfo::new(20, 20)));
+        }
+
+        #[test]
+        fn test_handle() {
+            let mut slots = vec![MemorySlot::empty(); SLOT_COUNT];
+            let mut index = vec![IndexEntry::empty(); index_capacity(SLOT_COUNT)];
+            let counter = AtomicU64::new(1);
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, MEMORY_SIZE)
+                .with_generation_counter(&counter);
+
+            let python_id = PythonId(40);
+            assert_eq!(memory.add_object(python_id, 10), Ok(0));
+            let handle = memory.handle_of(python_id).unwrap();
+            assert_eq!(handle, ObjectHandle::new(python_id, 1));
+            assert_eq!(memory.attach_object(handle), Ok(ObjectInfo::new(0, 10)));
+            assert_eq!(
+                memory.handle_of(PythonId(41)),
+                Err(ArrayPoolError::ObjectNotFound(PythonId(41)))
+            );
+
+            // Release object and add a new one with same ID
+            memory.set_object_releasable(handle).unwrap();
+            memory.detach_object(handle).unwrap();
+            memory.detach_object(handle).unwrap();
+            assert_eq!(memory.add_object(python_id, 20), Ok(0));
+
+            let stale_error = Err(ArrayPoolError::StaleHandle(python_id));
+            assert_eq!(memory.attach_object(handle), stale_error);
+            assert_eq!(memory.detach_object(handle), stale_error.map(|_| ()));
+            assert_eq!(
+                memory.set_object_releasable(handle),
+                stale_error.map(|_| ())
+            );
+
+            // Generation is kept when object is moved
+            let new_handle = memory.handle_of(python_id).unwrap();
+            assert_eq!(new_handle, ObjectHandle::new(python_id, 2));
+            memory.detach_object(new_handle).unwrap();
+            memory.compact(|_, _, _, _| ());
+            assert_eq!(memory.handle_of(python_id), Ok(new_handle));
+            assert_eq!(memory.attach_object(python_id), Ok(ObjectInfo::new(0, 20)));
+        }
+
+        #[test]
+        fn test_mint_python_id() {
+            let mut slots = vec![MemorySlot::empty(); SLOT_COUNT];
+            let mut index = vec![IndexEntry::empty(); index_capacity(SLOT_COUNT)];
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, MEMORY_SIZE);
+            let counter = AtomicU64::new(FIRST_POOL_PYTHON_ID);
+
+            assert_eq!(
+                memory.mint_python_id(&counter),
+                PythonId(FIRST_POOL_PYTHON_ID)
+            );
+
+            // Used IDs are skipped
+            assert!(memory
+                .add_object(PythonId(FIRST_POOL_PYTHON_ID + 1), 10)
+                .is_ok());
+            assert_eq!(
+                memory.mint_python_id(&counter),
+                PythonId(FIRST_POOL_PYTHON_ID + 2)
+            );
+
+            let counter = AtomicU64::new(u64::MAX);
+            assert_eq!(memory.mint_python_id(&counter), PythonId(u64::MAX));
+            assert_eq!(
+                memory.mint_python_id(&counter),
+                PythonId(FIRST_POOL_PYTHON_ID)
+            );
+            assert_eq!(
+                memory.mint_python_id(&counter),
+                PythonId(FIRST_POOL_PYTHON_ID + 2)
+            );
+        }
+
+        #[test]
+        fn test_add_described_object() {
+            let mut slots = vec![MemorySlot::empty(); SLOT_COUNT];
+            let mut index = vec![IndexEntry::empty(); index_capacity(SLOT_COUNT)];
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, MEMORY_SIZE)
+                .with_alignment(64);
+
+            assert_eq!(memory.add_object(PythonId(40), 64), Ok(0));
+            assert_eq!(memory.descriptor_info_of(PythonId(40)), Ok(None));
+
+            // Descriptor is stored just before aligned data
+            let data_offset = (64 + DESCRIPTOR_SIZE).next_multiple_of(64);
+            assert_eq!(
+                memory.add_described_object(PythonId(41), 100, 64),
+                Ok(data_offset)
+            );
+            assert_eq!(
+                memory.descriptor_info_of(PythonId(41)),
+                Ok(Some(ObjectInfo::new(
+                    data_offset - DESCRIPTOR_SIZE,
+                    DESCRIPTOR_SIZE
+                )))
+            );
+            assert_eq!(
+                memory.info_of(PythonId(41)),
+                Some(ObjectInfo::new(data_offset, 100))
+            );
+            assert_eq!(
+                memory.descriptor_info_of(PythonId(42)),
+                Err(ArrayPoolError::ObjectNotFound(PythonId(42)))
+            );
+
+            // Descriptor is moved with object (hole keeps padding of data)
+            memory.set_object_releasable(PythonId(40)).unwrap();
+            memory.detach_object(PythonId(40)).unwrap();
+            memory.detach_object(PythonId(41)).unwrap();
+
+            let mut moves = Vec::new();
+            memory.compact(|_segment, from, to, size| moves.push((from, to, size)));
+            let new_data_offset = DESCRIPTOR_SIZE.next_multiple_of(64);
+            assert_eq!(
+                moves,
+                vec![(
+                    data_offset - DESCRIPTOR_SIZE,
+                    new_data_offset - DESCRIPTOR_SIZE,
+                    DESCRIPTOR_SIZE + 100
+                )]
+            );
+            let data_offset = new_data_offset;
+            assert_eq!(
+                memory.info_of(PythonId(41)),
+                Some(ObjectInfo::new(data_offset, 100))
+            );
+        }
+
+        #[test]
+        fn test_remove_object() {
+            let mut slots = vec![MemorySlot::empty(); SLOT_COUNT];
+            let mut index = vec![IndexEntry::empty(); index_capacity(SLOT_COUNT)];
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, MEMORY_SIZE);
+
+            assert_eq!(memory.add_object(PythonId(40), 10), Ok(0));
+            assert_eq!(memory.add_object(PythonId(41), 10), Ok(10));
+            assert_eq!(
+                memory.attach_object(PythonId(40)),
+                Ok(ObjectInfo::new(0, 10))
+            );
+
+            assert_eq!(memory.remove_object(PythonId(40)), Ok(()));
+            assert_eq!(memory.info_of(PythonId(40)), None);
+            assert_eq!(memory.slots[0], MemorySlot::with_size(10));
+            assert_eq!(
+                memory.remove_object(PythonId(40)),
+                Err(ArrayPoolError::ObjectNotFound(PythonId(40)))
+            );
+            assert_eq!(memory.info_of(PythonId(41)), Some(ObjectInfo::new(10, 10)));
+        }
+
+        #[test]
+        fn test_release_event() {
+            let mut slots = vec![MemorySlot::empty(); SLOT_COUNT];
+            let mut index = vec![IndexEntry::empty(); index_capacity(SLOT_COUNT)];
+            let event = Event::new();
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, MEMORY_SIZE)
+                .with_release_event(&event);
+
+            assert_eq!(memory.add_object(PythonId(40), 10), Ok(0));
+            assert_eq!(memory.add_object(PythonId(41), 10), Ok(10));
+            memory.attach_object(PythonId(40)).unwrap();
+            assert_eq!(event.sequence(), 0);
+
+            // Event is only notified when object memory is released
+            memory.set_object_releasable(PythonId(40)).unwrap();
+            memory.detach_object(PythonId(40)).unwrap();
+            assert_eq!(event.sequence(), 0);
+            memory.detach_object(PythonId(40)).unwrap();
+            assert_eq!(event.sequence(), 1);
+
+            memory.remove_object(PythonId(41)).unwrap();
+            assert_eq!(event.sequence(), 2);
+
+            // Shrinking object releases end of its block
+            assert_eq!(memory.add_object(PythonId(42), 10), Ok(0));
+            memory.resize_object(PythonId(42), 5, |_, _| ()).unwrap();
+            assert_eq!(event.sequence(), 3);
+        }
+
+        #[test]
+        fn test_resize_in_place() {
+            let mut slots = vec![MemorySlot::empty(); SLOT_COUNT];
+            let mut index = vec![IndexEntry::empty(); index_capacity(SLOT_COUNT)];
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, MEMORY_SIZE);
+            let no_copy = |_, _| panic!("object must not be moved");
+
+            assert_eq!(memory.add_object(PythonId(40), 100), Ok(0));
+
+            // Shrink then grow into next free block
+            assert_eq!(
+                memory.resize_object(PythonId(40), 50, no_copy),
+                Ok(ObjectInfo::new(0, 50))
+            );
+            assert_eq!(memory.slots[1].offset, 50);
+            assert_eq!(
+                memory.resize_object(PythonId(40), 200, no_copy),
+                Ok(ObjectInfo::new(0, 200))
+            );
+            assert_eq!(memory.slots[1].offset, 200);
+
+            // Shrink object followed by another object
+            assert_eq!(memory.add_object(PythonId(41), 10), Ok(200));
+            assert_eq!(
+                memory.resize_object(PythonId(40), 150, no_copy),
+                Ok(ObjectInfo::new(0, 150))
+            );
+            assert_eq!(memory.slots[1], MemorySlot::with_size(50).set_offset(150));
+            assert_eq!(memory.info_of(PythonId(41)), Some(ObjectInfo::new(200, 10)));
+
+            // Grow using whole free block
+            assert_eq!(
+                memory.resize_object(PythonId(40), 200, no_copy),
+                Ok(ObjectInfo::new(0, 200))
+            );
+            assert_eq!(memory.slots[1].python_id, PythonId(41));
+            assert_eq!(memory.info_of(PythonId(41)), Some(ObjectInfo::new(200, 10)));
+            assert_eq!(
+                memory.resize_object(PythonId(42), 10, no_copy),
+                Err(ArrayPoolError::ObjectNotFound(PythonId(42)))
+            );
+        }
+
+        #[test]
+        fn test_resize_move() {
+            let mut slots = vec![MemorySlot::empty(); SLOT_COUNT];
+            let mut index = vec![IndexEntry::empty(); index_capacity(SLOT_COUNT)];
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, MEMORY_SIZE)
+                .with_alignment(64);
+
+            assert_eq!(memory.add_object(PythonId(40), 100), Ok(0));
+            assert_eq!(memory.add_object(PythonId(41), 10), Ok(128));
+
+            let mut copies = Vec::new();
+            assert_eq!(
+                memory.resize_object(PythonId(40), 1000, |from, to| copies.push((from, to))),
+                Ok(ObjectInfo::new(192, 1000))
+            );
+            assert_eq!(
+                copies,
+                vec![(ObjectInfo::new(0, 100), ObjectInfo::new(192, 1000))]
+            );
+
+            // Object keeps its state and old block is free
+            assert_eq!(memory.slots[0], MemorySlot::with_size(100));
+            assert_eq!(memory.slots[2].refcount, 1);
+            assert_eq!(
+                memory.info_of(PythonId(40)),
+                Some(ObjectInfo::new(192, 1000))
+            );
+            assert_eq!(memory.info_of(PythonId(41)), Some(ObjectInfo::new(128, 10)));
+
+            assert_eq!(
+                memory.resize_object(PythonId(41), MEMORY_SIZE, |_, _| ()),
+                Err(ArrayPoolError::NoSpaceLeft)
+            );
+        }
+
+        #[test]
+        fn test_resize_object_in_use() {
+            let mut slots = vec![MemorySlot::empty(); SLOT_COUNT];
+            let mut index = vec![IndexEntry::empty(); index_capacity(SLOT_COUNT)];
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, MEMORY_SIZE);
+            let no_copy = |_, _| panic!("object must not be moved");
+
+            assert_eq!(memory.add_object(PythonId(40), 100), Ok(0));
+            assert_eq!(memory.add_object(PythonId(41), 10), Ok(100));
+
+            // Object attached twice
+            memory.attach_object(PythonId(40)).unwrap();
+            assert_eq!(
+                memory.resize_object(PythonId(40), 200, no_copy),
+                Err(ArrayPoolError::ObjectInUse(PythonId(40)))
+            );
+            memory.detach_object(PythonId(40)).unwrap();
+
+            // Object used by another process
+            memory.slots[0].add_holder(process::id() + 1);
+            memory.slots[0].remove_holder(process::id());
+            assert_eq!(
+                memory.resize_object(PythonId(40), 200, no_copy),
+                Err(ArrayPoolError::ObjectInUse(PythonId(40)))
+            );
+            memory.slots[0].add_holder(process::id());
+            memory.slots[0].remove_holder(process::id() + 1);
+
+            // Object data handed out without reference
+            memory.pinned_info_of(PythonId(40)).unwrap();
+            assert_eq!(
+                memory.resize_object(PythonId(40), 200, no_copy),
+                Err(ArrayPoolError::ObjectInUse(PythonId(40)))
+            );
+
+            // Resizing in place is still possible
+            assert_eq!(
+                memory.resize_object(PythonId(40), 50, no_copy),
+                Ok(ObjectInfo::new(0, 50))
+            );
+            assert_eq!(memory.info_of(PythonId(41)), Some(ObjectInfo::new(100, 10)));
+        }
+
+        #[test]
+        fn test_stats() {
+            let mut slots = vec![MemorySlot::empty(); SLOT_COUNT];
+            let mut index = vec![IndexEntry::empty(); index_capacity(SLOT_COUNT)];
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, MEMORY_SIZE);
+
+            let stats = memory.stats();
+            assert_eq!(stats.total_bytes, MEMORY_SIZE);
+            assert_eq!(stats.free_bytes, MEMORY_SIZE);
+            assert_eq!(stats.largest_free_block, MEMORY_SIZE);
+            assert_eq!(stats.free_block_count, 1);
+            assert_eq!(stats.slots_used, 1);
+            assert_eq!(stats.slot_count, SLOT_COUNT);
+            assert_eq!(stats.fragmentation(), 0.0);
+
+            assert_eq!(memory.add_object(PythonId(40), 1024), Ok(0));
+            assert_eq!(memory.add_object(PythonId(41), 1024), Ok(1024));
+            memory.set_object_releasable(PythonId(40)).unwrap();
+            memory.detach_object(PythonId(40)).unwrap();
+
+            assert_eq!(
+                memory.stats(),
+                PoolStats {
+                    total_bytes: MEMORY_SIZE,
+                    used_bytes: 1024,
+                    free_bytes: MEMORY_SIZE - 1024,
+                    largest_free_block: MEMORY_SIZE - 2048,
+                    free_block_count: 2,
+                    object_count: 1,
+                    slots_used: 3,
+                    slot_count: SLOT_COUNT,
+                }
+            );
+            assert_eq!(memory.stats().fragmentation(), 1.0 - 8.0 / 9.0);
+        }
+
         #[test]
         fn test_info_of_invalid_python_id() {
             let mut slots = vec![MemorySlot::empty(); SLOT_COUNT];
-            let memory = MemoryPool::from_uninit_slice(&mut slots, MEMORY_SIZE);
+            let mut index = vec![IndexEntry::empty(); index_capacity(SLOT_COUNT)];
+            let memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, MEMORY_SIZE);
             assert_eq!(memory.info_of(PythonId::empty()), None);
         }
 
         #[test]
         fn test_info_of_missing_obj() {
             let mut slots = vec![MemorySlot::empty(); SLOT_COUNT];
-            let memory = MemoryPool::from_uninit_slice(&mut slots, MEMORY_SIZE);
+            let mut index = vec![IndexEntry::empty(); index_capacity(SLOT_COUNT)];
+            let memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, MEMORY_SIZE);
             assert_eq!(memory.info_of(PythonId(42)), None);
         }
 
         #[test]
         fn test_info_of() {
             let mut slots = vec![MemorySlot::empty(); SLOT_COUNT];
-            let mut memory = MemoryPool::from_uninit_slice(&mut slots, MEMORY_SIZE);
+            let mut index = vec![IndexEntry::empty(); index_capacity(SLOT_COUNT)];
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, MEMORY_SIZE);
             let python_id = PythonId(42);
 
             assert_eq!(memory.info_of(python_id), None);
@@ -833,7 +2800,8 @@ mod tests {
         #[test]
         fn test_attach_object_invalid_python_id() {
             let mut slots = vec![MemorySlot::empty(); SLOT_COUNT];
-            let mut memory = MemoryPool::from_uninit_slice(&mut slots, MEMORY_SIZE);
+            let mut index = vec![IndexEntry::empty(); index_capacity(SLOT_COUNT)];
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, MEMORY_SIZE);
             assert_eq!(
                 memory.attach_object(PythonId::empty()),
                 Err(ArrayPoolError::InvalidPythonId)
@@ -843,19 +2811,57 @@ mod tests {
         #[test]
         fn test_attach_object_missing() {
             let mut slots = vec![MemorySlot::empty(); SLOT_COUNT];
-            let mut memory = MemoryPool::from_uninit_slice(&mut slots, MEMORY_SIZE);
+            let mut index = vec![IndexEntry::empty(); index_capacity(SLOT_COUNT)];
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, MEMORY_SIZE);
             assert_eq!(
                 memory.attach_object(PythonId(40)),
                 Err(ArrayPoolError::ObjectNotFound(PythonId(40)))
             );
         }
 
+        #[test]
+        fn test_reclaim_dead_processes() {
+            const DEAD_PID: u32 = u32::MAX;
+
+            let mut slots = vec![MemorySlot::empty(); SLOT_COUNT];
+            let mut index = vec![IndexEntry::empty(); index_capacity(SLOT_COUNT)];
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, MEMORY_SIZE);
+
+            for python_id in 40..43 {
+                assert!(memory.add_object(PythonId(python_id), 10).is_ok());
+            }
+
+            // 40: detached by creator, still attached by dead process
+            memory.slots[0].add_holder(DEAD_PID);
+            memory.slots[0].set_transfered();
+            assert_eq!(memory.detach_object(PythonId(40)), Ok(()));
+
+            // 41: created by dead process
+            memory.slots[1].source_pid = DEAD_PID;
+            memory.slots[1].holders[0].pid = DEAD_PID;
+
+            // 42: attached by dead process and by current process
+            memory.slots[2].add_holder(DEAD_PID);
+
+            assert_eq!(memory.reclaim_dead_processes(|pid| pid != DEAD_PID), 2);
+            assert_eq!(memory.info_of(PythonId(40)), None);
+            assert_eq!(memory.info_of(PythonId(41)), None);
+            assert_eq!(
+                memory.slots[..2],
+                [
+                    MemorySlot::with_size(20),
+                    MemorySlot::with_object_id(PythonId(42), 10).set_offset(20),
+                ]
+            );
+        }
+
         #[test]
         fn test_attach_object() {
             let python_id1 = PythonId(40);
             let python_id2 = PythonId(41);
             let mut slots = vec![MemorySlot::empty(); SLOT_COUNT];
-            let mut memory = MemoryPool::from_uninit_slice(&mut slots, MEMORY_SIZE);
+            let mut index = vec![IndexEntry::empty(); index_capacity(SLOT_COUNT)];
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, MEMORY_SIZE);
 
             assert!(memory.add_object(python_id1, 20).is_ok());
             assert!(memory.add_object(python_id2, 10).is_ok());
@@ -880,8 +2886,10 @@ mod tests {
                 memory.slots,
                 vec![
                     MemorySlot::with_object_id(python_id1, 20).set_refcount(4),
-                    MemorySlot::with_object_id(python_id2, 10).set_refcount(3),
-                    MemorySlot::with_size(MEMORY_SIZE - 20 - 10),
+                    MemorySlot::with_object_id(python_id2, 10)
+                        .set_offset(20)
+                        .set_refcount(3),
+                    MemorySlot::with_size(MEMORY_SIZE - 20 - 10).set_offset(30),
                     MemorySlot::empty(),
                 ]
             );
@@ -910,7 +2918,8 @@ mod tests {
         #[test]
         fn test_dump() -> anyhow::Result<()> {
             let mut slots = vec![MemorySlot::empty(); SLOT_COUNT];
-            let mut memory = MemoryPool::from_uninit_slice(&mut slots, MEMORY_SIZE);
+            let mut index = vec![IndexEntry::empty(); index_capacity(SLOT_COUNT)];
+            let mut memory = MemoryPool::from_uninit_slice(&mut slots, &mut index, MEMORY_SIZE);
 
             // Add
             memory.add_object(PythonId(40), 10)?;
@@ -1,43 +1,291 @@
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::{
+    sync::atomic::{AtomicBool, AtomicU32, Ordering},
+    time::{Duration, Instant},
+};
 
-/// Simple spin lock based on atomic u8
+/// Backoff steps spent spinning (step `n` spins `2^n` times).
+const SPIN_LIMIT: u32 = 6;
+
+/// Backoff steps spent yielding to other threads before sleeping.
+const YIELD_LIMIT: u32 = 10;
+
+/// Maximum sleep duration while waiting for lock.
+///
+/// Sleepers are woken when lock is released, but they also wake up regularly
+/// to check if lock owner is still running.
+const MAX_SLEEP: Duration = Duration::from_millis(2);
+
+/// Check if a process is still running.
+///
+/// Process IDs can be reused, so a dead process may be reported alive.
+pub(crate) fn process_alive(pid: u32) -> bool {
+    // Signal 0 only checks process existence (EPERM: process is owned by another user)
+    let result = unsafe { libc::kill(pid as libc::pid_t, 0) };
+    result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Block until value of `atomic` is not `expected` anymore, `futex_wake` is
+/// called or `timeout` expires.
+///
+/// Spurious wake ups can occur, so caller must check value again.
+#[cfg(target_os = "linux")]
+pub(crate) fn futex_wait(atomic: &AtomicU32, expected: u32, timeout: Option<Duration>) {
+    let timespec = timeout.map(|x| libc::timespec {
+        tv_sec: x.as_secs() as libc::time_t,
+        tv_nsec: x.subsec_nanos() as libc::c_long,
+    });
+
+    // Shared futex (no FUTEX_PRIVATE_FLAG), since atomic is stored in shm
+    unsafe {
+        libc::syscall(
+            libc::SYS_futex,
+            atomic as *const AtomicU32,
+            libc::FUTEX_WAIT,
+            expected,
+            timespec
+                .as_ref()
+                .map_or(std::ptr::null(), |x| x as *const libc::timespec),
+        );
+    }
+}
+
+/// Wake up to `count` processes blocked in `futex_wait` on `atomic`.
+#[cfg(target_os = "linux")]
+pub(crate) fn futex_wake(atomic: &AtomicU32, count: u32) {
+    unsafe {
+        libc::syscall(
+            libc::SYS_futex,
+            atomic as *const AtomicU32,
+            libc::FUTEX_WAKE,
+            count.min(i32::MAX as u32),
+        );
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn futex_wait(atomic: &AtomicU32, expected: u32, timeout: Option<Duration>) {
+    if atomic.load(Ordering::Acquire) == expected {
+        std::thread::sleep(timeout.unwrap_or(MAX_SLEEP).min(MAX_SLEEP));
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn futex_wake(_atomic: &AtomicU32, _count: u32) {}
+
+/// Exponential backoff used while waiting for a value to change.
+///
+/// Waiter first spins, then yields to other threads and finally sleeps.
+#[derive(Debug, Default)]
+pub(crate) struct Backoff {
+    step: u32,
+}
+
+impl Backoff {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check if waiter now sleeps between attempts.
+    pub fn is_sleeping(&self) -> bool {
+        self.step > YIELD_LIMIT
+    }
+
+    /// Wait before next attempt.
+    ///
+    /// Once sleeping, waiter is blocked until value of `atomic` is not
+    /// `expected` anymore (and at most until `deadline`).
+    pub fn snooze(&mut self, atomic: &AtomicU32, expected: u32, deadline: Option<Instant>) {
+        if self.step <= SPIN_LIMIT {
+            for _ in 0..1 << self.step {
+                std::hint::spin_loop();
+            }
+        } else if self.step <= YIELD_LIMIT {
+            std::thread::yield_now();
+        } else {
+            let sleep = deadline.map_or(MAX_SLEEP, |x| {
+                x.saturating_duration_since(Instant::now()).min(MAX_SLEEP)
+            });
+            futex_wait(atomic, expected, Some(sleep));
+        }
+
+        if !self.is_sleeping() {
+            self.step += 1;
+        }
+    }
+}
+
+/// Inter-process event processes can wait for.
+///
+/// Each notification increments a sequence number and waiters sleep on a
+/// futex until it changes. Sequence must be read before checking awaited
+/// condition, so a notification sent in between is not missed.
+#[derive(Debug)]
+#[repr(C)]
+pub struct Event {
+    sequence: AtomicU32,
+    waiters: AtomicU32,
+}
+
+impl Event {
+    /// Init event
+    pub const fn new() -> Self {
+        Self {
+            sequence: AtomicU32::new(0),
+            waiters: AtomicU32::new(0),
+        }
+    }
+
+    /// Get current sequence number.
+    pub fn sequence(&self) -> u32 {
+        self.sequence.load(Ordering::SeqCst)
+    }
+
+    /// Block until event is notified after `sequence` has been read, or until
+    /// `timeout` expires.
+    pub fn wait(&self, sequence: u32, timeout: Duration) {
+        self.waiters.fetch_add(1, Ordering::SeqCst);
+        if self.sequence() == sequence {
+            futex_wait(&self.sequence, sequence, Some(timeout));
+        }
+        self.waiters.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    /// Wake up every waiter.
+    pub fn notify_all(&self) {
+        self.sequence.fetch_add(1, Ordering::SeqCst);
+        if self.waiters.load(Ordering::SeqCst) > 0 {
+            futex_wake(&self.sequence, u32::MAX);
+        }
+    }
+}
+
+/// Inter-process spin lock recovering from owner death.
+///
+/// Lock word contains ID of owner process (0 if unlocked). When owner process
+/// is not running anymore, lock is taken over by next process trying to
+/// acquire it and data it protects is flagged as possibly inconsistent.
+///
+/// Waiters use exponential backoff and end up sleeping on a futex, so
+/// contended lock does not burn CPU.
 ///
 /// See: https://doc.rust-lang.org/nomicon/atomics.html
 #[derive(Debug)]
-pub struct SimpleSpinLock {
-    lock: AtomicBool,
+#[repr(C)]
+pub struct RobustLock {
+    owner: AtomicU32,
+    waiters: AtomicU32,
+    inconsistent: AtomicBool,
+    _padding: [u8; 7],
 }
 
-impl SimpleSpinLock {
-    /// Init spin lock
+// Event and lock are stored in segment header
+const _: () = assert!(std::mem::size_of::<Event>() == 8);
+const _: () = assert!(std::mem::size_of::<RobustLock>() == 16);
+
+impl RobustLock {
+    /// Init lock
     pub const fn new() -> Self {
         Self {
-            lock: AtomicBool::new(false),
+            owner: AtomicU32::new(0),
+            waiters: AtomicU32::new(0),
+            inconsistent: AtomicBool::new(false),
+            _padding: [0; 7],
         }
     }
 
-    pub fn lock(&self) -> SimpleSpinLockGuard<'_> {
-        while self
-            .lock
-            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Acquire)
-            .is_err()
-        {}
+    /// Acquire lock, taking it over if owner process died.
+    pub fn lock(&self) -> RobustLockGuard<'_> {
+        self.lock_until(None)
+            .expect("lock without deadline cannot time out")
+    }
+
+    /// Acquire lock, giving up if it cannot be acquired within `timeout`.
+    pub fn try_lock_for(&self, timeout: Duration) -> Option<RobustLockGuard<'_>> {
+        self.lock_until(Instant::now().checked_add(timeout))
+    }
+
+    fn lock_until(&self, deadline: Option<Instant>) -> Option<RobustLockGuard<'_>> {
+        let pid = std::process::id();
+        let mut backoff = Backoff::new();
+
+        loop {
+            let owner =
+                match self
+                    .owner
+                    .compare_exchange_weak(0, pid, Ordering::Acquire, Ordering::Relaxed)
+                {
+                    Ok(_) => break,
+                    Err(owner) => owner,
+                };
+
+            // Owner liveness is only checked once waiting long enough
+            if owner != 0
+                && backoff.is_sleeping()
+                && !process_alive(owner)
+                && self.take_over(owner, pid)
+            {
+                break;
+            }
+
+            if deadline.is_some_and(|x| Instant::now() >= x) {
+                return None;
+            }
+
+            if owner != 0 && backoff.is_sleeping() {
+                self.waiters.fetch_add(1, Ordering::SeqCst);
+                backoff.snooze(&self.owner, owner, deadline);
+                self.waiters.fetch_sub(1, Ordering::SeqCst);
+            } else {
+                backoff.snooze(&self.owner, owner, deadline);
+            }
+        }
+
+        Some(RobustLockGuard { lock: self })
+    }
 
-        SimpleSpinLockGuard { lock: &self.lock }
+    /// Take lock from a dead owner.
+    fn take_over(&self, owner: u32, pid: u32) -> bool {
+        let taken = self
+            .owner
+            .compare_exchange(owner, pid, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok();
+        if taken {
+            self.inconsistent.store(true, Ordering::Relaxed);
+        }
+        taken
     }
 
     pub fn is_locked(&self) -> bool {
-        self.lock.load(Ordering::Relaxed)
+        self.owner.load(Ordering::Relaxed) != 0
     }
 }
 
-pub struct SimpleSpinLockGuard<'a> {
-    lock: &'a AtomicBool,
+/// Guard releasing lock when dropped.
+pub struct RobustLockGuard<'a> {
+    lock: &'a RobustLock,
 }
 
-impl<'a> Drop for SimpleSpinLockGuard<'a> {
+impl<'a> RobustLockGuard<'a> {
+    /// Check if a previous owner died while holding lock.
+    ///
+    /// Protected data may then be inconsistent. Flag is kept (and reported to
+    /// next owners) until [`RobustLockGuard::mark_consistent`] is called.
+    pub fn owner_died(&self) -> bool {
+        self.lock.inconsistent.load(Ordering::Relaxed)
+    }
+
+    /// Mark protected data as consistent again.
+    pub fn mark_consistent(&self) {
+        self.lock.inconsistent.store(false, Ordering::Relaxed);
+    }
+}
+
+impl<'a> Drop for RobustLockGuard<'a> {
     fn drop(&mut self) {
-        self.lock.store(false, Ordering::Release);
+        self.lock.owner.store(0, Ordering::SeqCst);
+        if self.lock.waiters.load(Ordering::SeqCst) > 0 {
+            futex_wake(&self.lock.owner, 1);
+        }
     }
 }
 
@@ -45,17 +293,108 @@ impl<'a> Drop for SimpleSpinLockGuard<'a> {
 mod tests {
     use super::*;
 
+    /// Get ID of a process which is not running anymore.
+    fn dead_pid() -> u32 {
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        child.wait().unwrap();
+        child.id()
+    }
+
     #[test]
     fn test_mutex() {
-        let mutex = SimpleSpinLock::new();
+        let mutex = RobustLock::new();
 
         assert!(!mutex.is_locked());
 
         {
-            let _guard = mutex.lock();
+            let guard = mutex.lock();
             assert!(mutex.is_locked());
+            assert!(!guard.owner_died());
         }
 
         assert!(!mutex.is_locked());
     }
+
+    #[test]
+    fn test_try_lock_for() {
+        let mutex = RobustLock::new();
+
+        let guard = mutex.try_lock_for(Duration::from_millis(10));
+        assert!(guard.is_some());
+        assert!(mutex.try_lock_for(Duration::from_millis(10)).is_none());
+
+        drop(guard);
+        assert!(mutex.try_lock_for(Duration::ZERO).is_some());
+    }
+
+    #[test]
+    fn test_contention() {
+        let mutex = RobustLock::new();
+        let counter = AtomicU32::new(0);
+
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for _ in 0..1000 {
+                        let _guard = mutex.lock();
+                        // Non atomic increment: lost updates if lock is broken
+                        let value = counter.load(Ordering::Relaxed);
+                        std::hint::spin_loop();
+                        counter.store(value + 1, Ordering::Relaxed);
+                    }
+                });
+            }
+        });
+
+        assert_eq!(counter.load(Ordering::Relaxed), 8000);
+        assert!(!mutex.is_locked());
+    }
+
+    #[test]
+    fn test_event() {
+        let event = Event::new();
+
+        // Event already notified since sequence has been read
+        let sequence = event.sequence();
+        event.notify_all();
+        let start = Instant::now();
+        event.wait(sequence, Duration::from_secs(10));
+        assert!(start.elapsed() < Duration::from_secs(10));
+
+        std::thread::scope(|scope| {
+            let sequence = event.sequence();
+            scope.spawn(|| {
+                std::thread::sleep(Duration::from_millis(20));
+                event.notify_all();
+            });
+            event.wait(sequence, Duration::from_secs(10));
+            assert_ne!(event.sequence(), sequence);
+        });
+    }
+
+    #[test]
+    fn test_process_alive() {
+        assert!(process_alive(std::process::id()));
+        assert!(!process_alive(dead_pid()));
+    }
+
+    #[test]
+    fn test_owner_died() {
+        let mutex = RobustLock::new();
+        mutex.owner.store(dead_pid(), Ordering::Relaxed);
+
+        {
+            let guard = mutex.lock();
+            assert!(guard.owner_died());
+        }
+
+        // Flag is kept until data is marked as consistent
+        {
+            let guard = mutex.lock();
+            assert!(guard.owner_died());
+            guard.mark_consistent();
+        }
+        assert!(!mutex.lock().owner_died());
+        assert!(!mutex.is_locked());
+    }
 }
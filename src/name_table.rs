@@ -0,0 +1,238 @@
+/*! Table to find objects of pool by name. */
+
+use std::str;
+
+use crate::memory_info::{ArrayPoolError, ObjectHandle, PythonId};
+
+/// Maximum length in bytes of an object name.
+pub const MAX_NAME_LEN: usize = 63;
+
+/// Entry of name table.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(C)]
+pub struct NameEntry {
+    /// Python object ID (0 if entry is free).
+    python_id: PythonId,
+
+    /// Generation of named object.
+    generation: u64,
+
+    name_len: u8,
+    name: [u8; MAX_NAME_LEN],
+}
+
+// Name entries are part of segment format (see `doc/SEGMENT_FORMAT.md`)
+const _: () = {
+    use std::mem::{offset_of, size_of};
+    assert!(offset_of!(NameEntry, name_len) == 16);
+    assert!(size_of::<NameEntry>() == 80);
+};
+
+impl NameEntry {
+    /// Create empty entry.
+    pub const fn empty() -> Self {
+        Self {
+            python_id: PythonId(0),
+            generation: 0,
+            name_len: 0,
+            name: [0; MAX_NAME_LEN],
+        }
+    }
+
+    /// Check if entry is free.
+    const fn is_free(&self) -> bool {
+        self.python_id.0 == 0
+    }
+
+    /// Get name of entry.
+    fn name(&self) -> &str {
+        let name_len = (self.name_len as usize).min(MAX_NAME_LEN);
+        str::from_utf8(&self.name[..name_len]).unwrap_or_default()
+    }
+
+    /// Get handle of named object.
+    fn handle(&self) -> ObjectHandle {
+        ObjectHandle::new(self.python_id, self.generation)
+    }
+}
+
+/// Check name can be stored in name table.
+pub fn check_name(name: &str) -> Result<(), ArrayPoolError> {
+    if name.is_empty() || name.len() > MAX_NAME_LEN {
+        Err(ArrayPoolError::InvalidName)
+    } else {
+        Ok(())
+    }
+}
+
+/// Fixed size table mapping names to object handles.
+///
+/// Table is expected to be small, so entries are looked up linearly.
+#[derive(Debug, PartialEq, Eq)]
+pub struct NameTable<'a> {
+    entries: &'a mut [NameEntry],
+}
+
+impl<'a> NameTable<'a> {
+    /// Create struct from already init entries.
+    pub fn new(entries: &'a mut [NameEntry]) -> Self {
+        Self { entries }
+    }
+
+    /// Get handle of object registered with given name.
+    pub fn get(&self, name: &str) -> Option<ObjectHandle> {
+        self.iter()
+            .find(|(entry_name, _)| *entry_name == name)
+            .map(|(_, handle)| handle)
+    }
+
+    /// Register object with given name.
+    pub fn insert(&mut self, name: &str, handle: ObjectHandle) -> Result<(), ArrayPoolError> {
+        check_name(name)?;
+        handle.python_id().valid()?;
+
+        if self.get(name).is_some() {
+            return Err(ArrayPoolError::NameAlreadyExists);
+        }
+
+        let entry = self
+            .entries
+            .iter_mut()
+            .find(|x| x.is_free())
+            .ok_or(ArrayPoolError::NameTableFull)?;
+
+        *entry = NameEntry {
+            python_id: handle.python_id(),
+            generation: handle.generation(),
+            name_len: name.len() as u8,
+            name: [0; MAX_NAME_LEN],
+        };
+        entry.name[..name.len()].copy_from_slice(name.as_bytes());
+
+        Ok(())
+    }
+
+    /// Remove name from table.
+    pub fn remove(&mut self, name: &str) -> Option<ObjectHandle> {
+        let entry = self
+            .entries
+            .iter_mut()
+            .find(|x| !x.is_free() && x.name() == name)?;
+        let handle = entry.handle();
+        *entry = NameEntry::empty();
+        Some(handle)
+    }
+
+    /// Remove every entry for which `f` returns false.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(ObjectHandle) -> bool,
+    {
+        for entry in self.entries.iter_mut().filter(|x| !x.is_free()) {
+            if !f(entry.handle()) {
+                *entry = NameEntry::empty();
+            }
+        }
+    }
+
+    /// Iterate over registered names and handles.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, ObjectHandle)> {
+        self.entries
+            .iter()
+            .filter(|x| !x.is_free())
+            .map(|x| (x.name(), x.handle()))
+    }
+
+    /// Get number of registered names.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Check if table is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.iter().all(|x| x.is_free())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_and_get() {
+        let mut entries = vec![NameEntry::empty(); 4];
+        let mut names = NameTable::new(&mut entries);
+        assert!(names.is_empty());
+
+        let handle1 = ObjectHandle::new(PythonId(40), 1);
+        let handle2 = ObjectHandle::new(PythonId(41), 2);
+        assert_eq!(names.insert("weights", handle1), Ok(()));
+        assert_eq!(names.insert("lookup_table", handle2), Ok(()));
+        assert_eq!(
+            names.insert("weights", handle2),
+            Err(ArrayPoolError::NameAlreadyExists)
+        );
+
+        assert_eq!(names.len(), 2);
+        assert_eq!(names.get("weights"), Some(handle1));
+        assert_eq!(names.get("lookup_table"), Some(handle2));
+        assert_eq!(names.get("other"), None);
+        assert_eq!(
+            names.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+            ["weights", "lookup_table"]
+        );
+    }
+
+    #[test]
+    fn test_insert_invalid() {
+        let mut entries = vec![NameEntry::empty(); 1];
+        let mut names = NameTable::new(&mut entries);
+        let handle = ObjectHandle::new(PythonId(40), 1);
+
+        assert_eq!(names.insert("", handle), Err(ArrayPoolError::InvalidName));
+        assert_eq!(
+            names.insert(&"x".repeat(MAX_NAME_LEN + 1), handle),
+            Err(ArrayPoolError::InvalidName)
+        );
+        assert_eq!(
+            names.insert("a", ObjectHandle::new(PythonId(0), 1)),
+            Err(ArrayPoolError::InvalidPythonId)
+        );
+
+        assert_eq!(names.insert(&"x".repeat(MAX_NAME_LEN), handle), Ok(()));
+        assert_eq!(
+            names.insert("b", handle),
+            Err(ArrayPoolError::NameTableFull)
+        );
+    }
+
+    #[test]
+    fn test_remove_and_retain() {
+        let mut entries = vec![NameEntry::empty(); 4];
+        let mut names = NameTable::new(&mut entries);
+
+        for python_id in 1..=4 {
+            let handle = ObjectHandle::new(PythonId(python_id), python_id);
+            names.insert(&format!("obj{python_id}"), handle).unwrap();
+        }
+
+        assert_eq!(
+            names.remove("obj2"),
+            Some(ObjectHandle::new(PythonId(2), 2))
+        );
+        assert_eq!(names.remove("obj2"), None);
+
+        names.retain(|handle| handle.python_id() != PythonId(3));
+        assert_eq!(
+            names.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+            ["obj1", "obj4"]
+        );
+
+        // Free entries can be reused
+        assert_eq!(
+            names.insert("obj5", ObjectHandle::new(PythonId(5), 5)),
+            Ok(())
+        );
+        assert_eq!(names.len(), 3);
+    }
+}
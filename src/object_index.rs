@@ -0,0 +1,240 @@
+/*! Hash index to find memory slot of a python object. */
+
+use crate::memory_info::PythonId;
+
+/// Entry of object index.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(C)]
+pub struct IndexEntry {
+    /// Python object ID (0 if entry is free).
+    python_id: PythonId,
+
+    /// Index of memory slot of object.
+    slot_index: u64,
+}
+
+// Index entries are part of segment format (see `doc/SEGMENT_FORMAT.md`)
+const _: () = assert!(std::mem::size_of::<IndexEntry>() == 16);
+
+impl IndexEntry {
+    /// Create empty entry.
+    pub const fn empty() -> Self {
+        Self {
+            python_id: PythonId(0),
+            slot_index: 0,
+        }
+    }
+
+    /// Check if entry is free.
+    const fn is_free(&self) -> bool {
+        self.python_id.0 == 0
+    }
+}
+
+/// Get number of index entries required to index `slot_count` slots.
+///
+/// Index is kept at most half full so probe sequences stay short.
+pub const fn index_capacity(slot_count: usize) -> usize {
+    (slot_count * 2).next_power_of_two()
+}
+
+/// Mix bits of python ID.
+///
+/// Python IDs are often derived from memory addresses and share lot of low bits.
+/// See: https://xorshift.di.unimi.it/splitmix64.c
+const fn hash(python_id: PythonId) -> u64 {
+    let mut x = python_id.0;
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+/// Open addressing hash table (with linear probing) mapping python ID to slot index.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ObjectIndex<'a> {
+    entries: &'a mut [IndexEntry],
+}
+
+impl<'a> ObjectIndex<'a> {
+    /// Create struct from already init entries.
+    pub fn new(entries: &'a mut [IndexEntry]) -> Self {
+        assert!(entries.len().is_power_of_two());
+        Self { entries }
+    }
+
+    /// Get index of first entry to probe for given object.
+    fn home_of(&self, python_id: PythonId) -> usize {
+        hash(python_id) as usize & (self.entries.len() - 1)
+    }
+
+    /// Get position in table of given object.
+    fn position_of(&self, python_id: PythonId) -> Option<usize> {
+        let mask = self.entries.len() - 1;
+        let mut pos = self.home_of(python_id);
+
+        for _ in 0..self.entries.len() {
+            let entry = &self.entries[pos];
+            if entry.is_free() {
+                return None;
+            }
+            if entry.python_id == python_id {
+                return Some(pos);
+            }
+            pos = (pos + 1) & mask;
+        }
+
+        None
+    }
+
+    /// Get slot index of given object.
+    pub fn get(&self, python_id: PythonId) -> Option<usize> {
+        self.position_of(python_id)
+            .map(|pos| self.entries[pos].slot_index as usize)
+    }
+
+    /// Add or update object slot index.
+    pub fn insert(&mut self, python_id: PythonId, slot_index: usize) {
+        debug_assert!(python_id.0 != 0);
+        let mask = self.entries.len() - 1;
+        let mut pos = self.home_of(python_id);
+
+        for _ in 0..self.entries.len() {
+            let entry = &mut self.entries[pos];
+            if entry.is_free() || entry.python_id == python_id {
+                *entry = IndexEntry {
+                    python_id,
+                    slot_index: slot_index as u64,
+                };
+                return;
+            }
+            pos = (pos + 1) & mask;
+        }
+
+        panic!("object index is full");
+    }
+
+    /// Remove object from index.
+    ///
+    /// Following entries are shifted back so no tombstone is required.
+    pub fn remove(&mut self, python_id: PythonId) -> Option<usize> {
+        let mask = self.entries.len() - 1;
+        let mut hole = self.position_of(python_id)?;
+        let slot_index = self.entries[hole].slot_index as usize;
+
+        let mut pos = (hole + 1) & mask;
+        while pos != hole && !self.entries[pos].is_free() {
+            // Move entry to hole if hole is between its home and its position.
+            let home = self.home_of(self.entries[pos].python_id);
+            if (pos.wrapping_sub(home) & mask) >= (pos.wrapping_sub(hole) & mask) {
+                self.entries[hole] = self.entries[pos];
+                hole = pos;
+            }
+            pos = (pos + 1) & mask;
+        }
+
+        self.entries[hole] = IndexEntry::empty();
+        Some(slot_index)
+    }
+
+    /// Remove every object from index.
+    pub fn clear(&mut self) {
+        self.entries.fill(IndexEntry::empty());
+    }
+
+    /// Get number of indexed objects.
+    pub fn len(&self) -> usize {
+        self.entries.iter().filter(|x| !x.is_free()).count()
+    }
+
+    /// Check if index is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.iter().all(|x| x.is_free())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capacity() {
+        assert_eq!(index_capacity(1), 2);
+        assert_eq!(index_capacity(4), 8);
+        assert_eq!(index_capacity(5), 16);
+        assert_eq!(index_capacity(5000), 16384);
+    }
+
+    #[test]
+    fn test_insert_and_get() {
+        let mut entries = vec![IndexEntry::empty(); 8];
+        let mut index = ObjectIndex::new(&mut entries);
+        assert!(index.is_empty());
+
+        index.insert(PythonId(40), 0);
+        index.insert(PythonId(41), 1);
+        index.insert(PythonId(42), 2);
+
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.get(PythonId(40)), Some(0));
+        assert_eq!(index.get(PythonId(41)), Some(1));
+        assert_eq!(index.get(PythonId(42)), Some(2));
+        assert_eq!(index.get(PythonId(43)), None);
+
+        // Update existing entry
+        index.insert(PythonId(41), 5);
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.get(PythonId(41)), Some(5));
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut entries = vec![IndexEntry::empty(); 8];
+        let mut index = ObjectIndex::new(&mut entries);
+
+        for python_id in 1..=4 {
+            index.insert(PythonId(python_id), python_id as usize);
+        }
+
+        assert_eq!(index.remove(PythonId(5)), None);
+        assert_eq!(index.remove(PythonId(2)), Some(2));
+        assert_eq!(index.remove(PythonId(2)), None);
+
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.get(PythonId(1)), Some(1));
+        assert_eq!(index.get(PythonId(2)), None);
+        assert_eq!(index.get(PythonId(3)), Some(3));
+        assert_eq!(index.get(PythonId(4)), Some(4));
+    }
+
+    #[test]
+    fn test_collisions() {
+        let mut entries = vec![IndexEntry::empty(); 64];
+        let mut index = ObjectIndex::new(&mut entries);
+
+        // Fill table up to limit and remove objects in random-ish order
+        for python_id in 1..=64 {
+            index.insert(PythonId(python_id), python_id as usize * 10);
+        }
+        for python_id in (1..=64).filter(|x| x % 3 == 0) {
+            assert_eq!(
+                index.remove(PythonId(python_id)),
+                Some(python_id as usize * 10)
+            );
+        }
+        for python_id in 1..=64 {
+            let expected = (python_id % 3 != 0).then(|| python_id as usize * 10);
+            assert_eq!(index.get(PythonId(python_id)), expected);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_insert_full() {
+        let mut entries = vec![IndexEntry::empty(); 2];
+        let mut index = ObjectIndex::new(&mut entries);
+
+        index.insert(PythonId(1), 0);
+        index.insert(PythonId(2), 0);
+        index.insert(PythonId(3), 0);
+    }
+}
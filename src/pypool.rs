@@ -0,0 +1,405 @@
+/*! Python class wrapping shared memory object pool. */
+
+// `#[pymethods]` of pyo3 0.16 defines `#[new]` wrapper as an `impl` nested in a
+// generated static, so lint cannot be allowed on the `impl` block itself.
+#![allow(non_local_definitions)]
+
+use std::{os::raw::c_schar, path::PathBuf, str::FromStr, sync::Arc, time::Duration};
+
+use pyo3::{
+    exceptions::PyValueError,
+    ffi::{PyBUF_WRITE, PyMemoryView_Check, PyMemoryView_FromMemory, Py_ssize_t},
+    prelude::*,
+    types::{PyDict, PyTuple},
+};
+
+use crate::{
+    descriptor::{ArrayDescriptor, ByteOrder},
+    memory_info::{AllocationStrategy, ObjectHandle, PythonId},
+    shm::{ObjectData, ShmError, ShmObjectPool, ShmObjectPoolBuilder},
+};
+
+#[pyclass(
+    name = "ShmObjectPool",
+    text_signature = "(*, slot_count = ..., data_size = ..., alignment = ..., strategy = ..., auto_compact = ..., max_segment_count = ..., name_count = ..., lock_timeout = ..., path = ...)"
+)]
+pub(crate) struct PyShmObjectPool {
+    pool: Arc<ShmObjectPool<'static>>,
+}
+
+#[pymethods]
+impl PyShmObjectPool {
+    #[new]
+    #[args(
+        _py_args = "*",
+        slot_count = "5000",
+        data_size = "524288000",
+        alignment = "64",
+        strategy = "\"first_fit\"",
+        auto_compact = "false",
+        max_segment_count = "1",
+        name_count = "256",
+        lock_timeout = "10.0",
+        path = "\"pyarraypool.seg\""
+    )]
+    #[allow(clippy::too_many_arguments)] // Mirrors python keyword arguments
+    fn new(
+        py: Python,
+        _py_args: &PyTuple,
+        slot_count: usize,
+        data_size: usize,
+        alignment: usize,
+        strategy: &str,
+        auto_compact: bool,
+        max_segment_count: usize,
+        name_count: usize,
+        lock_timeout: f64,
+        path: &str,
+    ) -> PyResult<Self> {
+        let path = PathBuf::from_str(path)?;
+        let lock_timeout = Duration::try_from_secs_f64(lock_timeout)
+            .map_err(|e| PyValueError::new_err(format!("invalid lock timeout: {e}")))?;
+
+        let builder = ShmObjectPoolBuilder::new()
+            .slot_count(slot_count)
+            .data_size(data_size)
+            .alignment(alignment)
+            .strategy(parse_strategy(strategy)?)
+            .auto_compact(auto_compact)
+            .max_segment_count(max_segment_count)
+            .name_count(name_count)
+            .lock_timeout(lock_timeout)
+            .segment_path(path);
+        let pool = py.allow_threads(|| builder.open_or_create())?;
+
+        Ok(Self {
+            pool: Arc::new(pool),
+        })
+    }
+
+    #[args(alignment = "None")]
+    fn add_object(
+        &self,
+        py: Python,
+        python_id: u64,
+        request_size: usize,
+        alignment: Option<usize>,
+    ) -> PyResult<PyObject> {
+        let alignment = alignment.unwrap_or_else(|| self.pool.alignment());
+        let data = py.allow_threads(|| {
+            self.pool
+                .add_object_aligned(PythonId(python_id), request_size, alignment)
+        })?;
+        Ok(self.pymemoryview_from_data(data))
+    }
+
+    fn add_object_blocking(
+        &self,
+        py: Python,
+        python_id: u64,
+        request_size: usize,
+        timeout: f64,
+    ) -> PyResult<PyObject> {
+        let timeout = parse_timeout(timeout)?;
+        let data = py.allow_threads(|| {
+            self.pool
+                .add_object_blocking(PythonId(python_id), request_size, timeout)
+        })?;
+        Ok(self.pymemoryview_from_data(data))
+    }
+
+    #[args(byte_order = "\"=\"")]
+    #[allow(clippy::too_many_arguments)] // Mirrors python keyword arguments
+    fn add_array(
+        &self,
+        py: Python,
+        python_id: u64,
+        request_size: usize,
+        dtype: &str,
+        shape: Vec<u64>,
+        strides: Vec<i64>,
+        byte_order: &str,
+    ) -> PyResult<PyObject> {
+        let descriptor =
+            ArrayDescriptor::new(dtype, parse_byte_order(byte_order)?, &shape, &strides)
+                .map_err(ShmError::from)?;
+
+        let data = py.allow_threads(|| {
+            self.pool
+                .add_described_object(PythonId(python_id), request_size, &descriptor)
+        })?;
+        Ok(self.pymemoryview_from_data(data))
+    }
+
+    #[args(alignment = "None")]
+    fn allocate(
+        &self,
+        py: Python,
+        request_size: usize,
+        alignment: Option<usize>,
+    ) -> PyResult<(u64, u64, PyObject)> {
+        let alignment = alignment.unwrap_or_else(|| self.pool.alignment());
+        let (handle, data) =
+            py.allow_threads(|| self.pool.allocate_aligned(request_size, alignment))?;
+        Ok((
+            handle.python_id().0,
+            handle.generation(),
+            self.pymemoryview_from_data(data),
+        ))
+    }
+
+    #[args(byte_order = "\"=\"")]
+    fn allocate_array(
+        &self,
+        py: Python,
+        request_size: usize,
+        dtype: &str,
+        shape: Vec<u64>,
+        strides: Vec<i64>,
+        byte_order: &str,
+    ) -> PyResult<(u64, u64, PyObject)> {
+        let descriptor =
+            ArrayDescriptor::new(dtype, parse_byte_order(byte_order)?, &shape, &strides)
+                .map_err(ShmError::from)?;
+
+        let (handle, data) =
+            py.allow_threads(|| self.pool.allocate_described(request_size, &descriptor))?;
+        Ok((
+            handle.python_id().0,
+            handle.generation(),
+            self.pymemoryview_from_data(data),
+        ))
+    }
+
+    fn add_named(
+        &self,
+        py: Python,
+        name: &str,
+        request_size: usize,
+    ) -> PyResult<(u64, u64, PyObject)> {
+        let (handle, data) = py.allow_threads(|| self.pool.add_named(name, request_size))?;
+        Ok((
+            handle.python_id().0,
+            handle.generation(),
+            self.pymemoryview_from_data(data),
+        ))
+    }
+
+    fn attach_named(&self, py: Python, name: &str) -> PyResult<(u64, u64, PyObject)> {
+        let (handle, data) = py.allow_threads(|| self.pool.attach_named(name))?;
+        Ok((
+            handle.python_id().0,
+            handle.generation(),
+            self.pymemoryview_from_data(data),
+        ))
+    }
+
+    fn lookup(&self, py: Python, name: &str) -> PyResult<Option<(u64, u64)>> {
+        Ok(py
+            .allow_threads(|| self.pool.lookup(name))?
+            .map(|handle| (handle.python_id().0, handle.generation())))
+    }
+
+    fn names(&self, py: Python) -> PyResult<Vec<String>> {
+        Ok(py.allow_threads(|| self.pool.names())?)
+    }
+
+    fn generation_of(&self, py: Python, python_id: u64) -> PyResult<u64> {
+        let handle = py.allow_threads(|| self.pool.handle_of(PythonId(python_id)))?;
+        Ok(handle.generation())
+    }
+
+    fn describe(&self, py: Python, python_id: u64) -> PyResult<Option<Py<PyDict>>> {
+        let descriptor = match py.allow_threads(|| self.pool.describe(PythonId(python_id)))? {
+            Some(descriptor) => descriptor,
+            None => return Ok(None),
+        };
+
+        let dict = PyDict::new(py);
+        dict.set_item("dtype", descriptor.dtype())?;
+        dict.set_item(
+            "byte_order",
+            char::from(descriptor.byte_order().as_raw()).to_string(),
+        )?;
+        dict.set_item("shape", PyTuple::new(py, descriptor.shape()))?;
+        dict.set_item("strides", PyTuple::new(py, descriptor.strides()))?;
+        Ok(Some(dict.into()))
+    }
+
+    #[args(generation = "0")]
+    fn attach_object(&self, py: Python, python_id: u64, generation: u64) -> PyResult<PyObject> {
+        let handle = ObjectHandle::new(PythonId(python_id), generation);
+        let data = py.allow_threads(|| self.pool.attach_object(handle))?;
+        Ok(self.pymemoryview_from_data(data))
+    }
+
+    fn add_objects(&self, py: Python, objects: Vec<(u64, usize)>) -> PyResult<Vec<PyObject>> {
+        let objects: Vec<_> = objects
+            .into_iter()
+            .map(|(python_id, request_size)| (PythonId(python_id), request_size))
+            .collect();
+
+        let data = py.allow_threads(|| self.pool.add_objects(&objects))?;
+        Ok(data
+            .into_iter()
+            .map(|x| self.pymemoryview_from_data(x))
+            .collect())
+    }
+
+    #[args(generations = "None")]
+    fn attach_objects(
+        &self,
+        py: Python,
+        python_ids: Vec<u64>,
+        generations: Option<Vec<u64>>,
+    ) -> PyResult<Vec<PyObject>> {
+        let handles = parse_handles(python_ids, generations)?;
+
+        let data = py.allow_threads(|| self.pool.attach_objects(&handles))?;
+        Ok(data
+            .into_iter()
+            .map(|x| self.pymemoryview_from_data(x))
+            .collect())
+    }
+
+    #[args(generations = "None")]
+    fn detach_objects(
+        &self,
+        py: Python,
+        python_ids: Vec<u64>,
+        generations: Option<Vec<u64>>,
+    ) -> PyResult<()> {
+        let handles = parse_handles(python_ids, generations)?;
+        Ok(py.allow_threads(|| self.pool.detach_objects(&handles))?)
+    }
+
+    #[args(generation = "0")]
+    fn attach_object_wait(
+        &self,
+        py: Python,
+        python_id: u64,
+        timeout: f64,
+        generation: u64,
+    ) -> PyResult<PyObject> {
+        let timeout = parse_timeout(timeout)?;
+        let handle = ObjectHandle::new(PythonId(python_id), generation);
+
+        let data = py.allow_threads(|| self.pool.attach_object_wait(handle, timeout))?;
+        Ok(self.pymemoryview_from_data(data))
+    }
+
+    #[args(generation = "0")]
+    fn detach_object(&self, py: Python, python_id: u64, generation: u64) -> PyResult<()> {
+        let handle = ObjectHandle::new(PythonId(python_id), generation);
+        Ok(py.allow_threads(|| self.pool.detach_object(handle))?)
+    }
+
+    #[args(generation = "0")]
+    fn set_object_releasable(&self, py: Python, python_id: u64, generation: u64) -> PyResult<()> {
+        let handle = ObjectHandle::new(PythonId(python_id), generation);
+        Ok(py.allow_threads(|| self.pool.set_object_releasable(handle))?)
+    }
+
+    fn resize_object(&self, py: Python, python_id: u64, new_size: usize) -> PyResult<PyObject> {
+        // Data is copied without GIL if object has to be moved
+        let data = py.allow_threads(|| self.pool.resize_object(PythonId(python_id), new_size))?;
+        Ok(self.pymemoryview_from_data(data))
+    }
+
+    fn memview_of(&self, py: Python, python_id: u64) -> Option<PyObject> {
+        let data = py.allow_threads(|| self.pool.slice_of(PythonId(python_id)))?;
+        Some(self.pymemoryview_from_data(data))
+    }
+
+    fn compact(&self, py: Python) -> PyResult<usize> {
+        // Data is copied without GIL
+        Ok(py.allow_threads(|| self.pool.compact())?)
+    }
+
+    fn reclaim_dead_processes(&self, py: Python) -> PyResult<usize> {
+        Ok(py.allow_threads(|| self.pool.reclaim_dead_processes())?)
+    }
+
+    fn stats(&self, py: Python) -> PyResult<Py<PyDict>> {
+        let stats = py.allow_threads(|| self.pool.stats())?;
+
+        let dict = PyDict::new(py);
+        dict.set_item("total_bytes", stats.total_bytes)?;
+        dict.set_item("used_bytes", stats.used_bytes)?;
+        dict.set_item("free_bytes", stats.free_bytes)?;
+        dict.set_item("largest_free_block", stats.largest_free_block)?;
+        dict.set_item("free_block_count", stats.free_block_count)?;
+        dict.set_item("object_count", stats.object_count)?;
+        dict.set_item("slots_used", stats.slots_used)?;
+        dict.set_item("slot_count", stats.slot_count)?;
+        dict.set_item("fragmentation", stats.fragmentation())?;
+        Ok(dict.into())
+    }
+
+    fn dump(&self, py: Python) -> PyResult<String> {
+        Ok(py.allow_threads(|| self.pool.dump())?)
+    }
+}
+
+impl PyShmObjectPool {
+    fn pymemoryview_from_data(&self, data: ObjectData) -> PyObject {
+        Python::with_gil(|py| unsafe {
+            let memview_ptr = PyMemoryView_FromMemory(
+                data.as_ptr() as *mut c_schar,
+                data.len() as Py_ssize_t,
+                PyBUF_WRITE,
+            );
+
+            assert!(!memview_ptr.is_null());
+            assert!(PyMemoryView_Check(memview_ptr) == 1);
+
+            py.from_owned_ptr::<PyAny>(memview_ptr)
+                .extract()
+                .expect("Fail to create PyObject")
+        })
+    }
+}
+
+fn parse_byte_order(value: &str) -> PyResult<ByteOrder> {
+    match value.as_bytes() {
+        [raw] => ByteOrder::from_raw(*raw),
+        _ => None,
+    }
+    .ok_or_else(|| PyValueError::new_err(format!("invalid byte order: {value}")))
+}
+
+fn parse_handles(
+    python_ids: Vec<u64>,
+    generations: Option<Vec<u64>>,
+) -> PyResult<Vec<ObjectHandle>> {
+    let generations = generations.unwrap_or_else(|| vec![0; python_ids.len()]);
+    if generations.len() != python_ids.len() {
+        return Err(PyValueError::new_err(format!(
+            "expected {} generations, got {}",
+            python_ids.len(),
+            generations.len()
+        )));
+    }
+
+    Ok(python_ids
+        .into_iter()
+        .zip(generations)
+        .map(|(python_id, generation)| ObjectHandle::new(PythonId(python_id), generation))
+        .collect())
+}
+
+fn parse_timeout(value: f64) -> PyResult<Duration> {
+    Duration::try_from_secs_f64(value)
+        .map_err(|e| PyValueError::new_err(format!("invalid timeout: {e}")))
+}
+
+fn parse_strategy(value: &str) -> PyResult<AllocationStrategy> {
+    match value {
+        "first_fit" => Ok(AllocationStrategy::FirstFit),
+        "best_fit" => Ok(AllocationStrategy::BestFit),
+        "segregated" => Ok(AllocationStrategy::Segregated),
+        _ => Err(PyValueError::new_err(format!(
+            "invalid allocation strategy: {value}"
+        ))),
+    }
+}
@@ -1,25 +1,165 @@
 /*! Helper around SHM management. */
 
 use std::{
-    cell::RefCell,
+    ffi::OsString,
     fmt,
     marker::PhantomData,
+    ops::{Deref, DerefMut},
     path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU32, AtomicU64, Ordering},
+        Mutex, MutexGuard, PoisonError,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use shared_memory::{Shmem, ShmemConf, ShmemError};
 use thiserror::Error;
 
 use crate::{
-    memory_info::{ArrayPoolError, MemoryPool, MemorySlot, ObjectInfo, PythonId},
-    mutex::{SimpleSpinLock, SimpleSpinLockGuard},
+    descriptor::{ArrayDescriptor, DESCRIPTOR_SIZE},
+    memory_info::{
+        check_alignment, AllocationStrategy, ArrayPoolError, MemoryPool, MemorySlot, ObjectHandle,
+        ObjectInfo, PoolStats, PythonId, DEFAULT_ALIGNMENT, FIRST_POOL_PYTHON_ID,
+    },
+    mutex::{futex_wake, process_alive, Backoff, Event, RobustLock, RobustLockGuard},
+    name_table::{check_name, NameEntry, NameTable},
+    object_index::{index_capacity, IndexEntry},
 };
 
-const SHM_HEADER_MAGIC: u64 = 0xFF45_9831_ABAB_0001;
-const SHM_VERSION: u8 = 1;
+mod migration;
+
+// Segment format only uses little-endian fields (see `doc/SEGMENT_FORMAT.md`)
+#[cfg(not(target_endian = "little"))]
+compile_error!("shm segment format requires a little-endian target");
+
+/// Magic value of fixed-width segment format.
+///
+/// Segments of previous format (with native width fields) use another magic
+/// value and are migrated when opened (see [`migration`]).
+const SHM_HEADER_MAGIC: u64 = 0xFF45_9831_ABAB_0002;
+const SHM_VERSION: u32 = 2;
+
+/// Pointer width of current process, segments of other widths are rejected.
+const POINTER_WIDTH: u8 = std::mem::size_of::<usize>() as u8;
+
+/// Default maximum duration to wait for pool lock.
+pub const DEFAULT_LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+
+const FLAG_HEADER_AUTO_COMPACT: u8 = 0x01;
+
+/// Segment is being initialized by its creator (mapping is zero filled).
+const STATE_INITIALIZING: u32 = 0;
+
+/// Segment has been initialized and can be used.
+const STATE_READY: u32 = 1;
+
+/// Segment index is stored as a `u16` in memory slots.
+const MAX_SEGMENT_COUNT: usize = u16::MAX as usize + 1;
+
+/// Segment size is rounded so slot table stored at its end is aligned.
+const SHM_SIZE_ALIGNMENT: usize = std::mem::align_of::<MemorySlot>();
 
 const SHM_HEADER_SIZE: usize = std::mem::size_of::<ShmHeader>();
 const MEMORY_SLOT_SIZE: usize = std::mem::size_of::<MemorySlot>();
+const INDEX_ENTRY_SIZE: usize = std::mem::size_of::<IndexEntry>();
+const NAME_ENTRY_SIZE: usize = std::mem::size_of::<NameEntry>();
+
+// Header is part of segment format (see `doc/SEGMENT_FORMAT.md`)
+const _: () = {
+    use std::mem::offset_of;
+    assert!(offset_of!(ShmHeader, version) == 8);
+    assert!(offset_of!(ShmHeader, strategy) == 13);
+    assert!(offset_of!(ShmHeader, creator_pid) == 16);
+    assert!(offset_of!(ShmHeader, segment_size) == 32);
+    assert!(offset_of!(ShmHeader, slot_count) == 64);
+    assert!(offset_of!(ShmHeader, next_python_id) == 112);
+    assert!(offset_of!(ShmHeader, lock) == 128);
+    assert!(offset_of!(ShmHeader, released) == 152);
+    assert!(SHM_HEADER_SIZE == 160);
+};
+
+/// Offsets of each region of segment.
+///
+/// Segment is made of: header | data | name table | object index | memory slots.
+///
+/// Name table, object index and memory slots are stored at the end of the
+/// segment, so slot table can grow by taking space from the end of data region.
+#[derive(Debug, Clone, Copy)]
+struct SegmentLayout {
+    data_offset: usize,
+    data_size: usize,
+    names_offset: usize,
+    name_count: usize,
+    index_offset: usize,
+    index_len: usize,
+    slots_offset: usize,
+    slot_count: usize,
+}
+
+impl SegmentLayout {
+    const fn new(slot_count: usize, name_count: usize, segment_size: usize) -> Self {
+        let index_len = index_capacity(slot_count);
+        let slots_offset = segment_size - slot_count * MEMORY_SLOT_SIZE;
+        let index_offset = slots_offset - index_len * INDEX_ENTRY_SIZE;
+        let names_offset = index_offset - name_count * NAME_ENTRY_SIZE;
+
+        Self {
+            data_offset: SHM_HEADER_SIZE,
+            data_size: names_offset - SHM_HEADER_SIZE,
+            names_offset,
+            name_count,
+            index_offset,
+            index_len,
+            slots_offset,
+            slot_count,
+        }
+    }
+
+    /// Get size of object index and memory slots for a given slot count.
+    const fn table_size(slot_count: usize) -> usize {
+        index_capacity(slot_count) * INDEX_ENTRY_SIZE + slot_count * MEMORY_SLOT_SIZE
+    }
+
+    /// Get segment size required to store a given slot count, name count and data size.
+    const fn segment_size(slot_count: usize, name_count: usize, data_size: usize) -> usize {
+        let size = SHM_HEADER_SIZE
+            + data_size
+            + name_count * NAME_ENTRY_SIZE
+            + Self::table_size(slot_count);
+        size.div_ceil(SHM_SIZE_ALIGNMENT) * SHM_SIZE_ALIGNMENT
+    }
+
+    /// Get index and slots arrays from segment base pointer.
+    ///
+    /// # Safety
+    ///
+    /// Pointer must point to a segment large enough to contains layout.
+    unsafe fn arrays<'a>(&self, raw_ptr: *mut u8) -> (&'a mut [MemorySlot], &'a mut [IndexEntry]) {
+        (
+            std::slice::from_raw_parts_mut(
+                raw_ptr.add(self.slots_offset) as *mut MemorySlot,
+                self.slot_count,
+            ),
+            std::slice::from_raw_parts_mut(
+                raw_ptr.add(self.index_offset) as *mut IndexEntry,
+                self.index_len,
+            ),
+        )
+    }
+
+    /// Get name table entries from segment base pointer.
+    ///
+    /// # Safety
+    ///
+    /// Pointer must point to a segment large enough to contains layout.
+    unsafe fn names<'a>(&self, raw_ptr: *mut u8) -> &'a mut [NameEntry] {
+        std::slice::from_raw_parts_mut(
+            raw_ptr.add(self.names_offset) as *mut NameEntry,
+            self.name_count,
+        )
+    }
+}
 
 /// Possible error that can occurs with shm module.
 #[derive(Debug, PartialEq, Eq, Clone, Error)]
@@ -28,346 +168,3105 @@ pub enum ShmError {
     #[error("invalid shm magic value")]
     InvalidShmMagicValue,
 
-    /// Header does not contains valid SHM version number.
-    #[error("invalid shm version")]
-    InvalidShmVersion,
+    /// Segment has been written with a format version which cannot be read or migrated.
+    #[error("unsupported shm version: {0}")]
+    UnsupportedShmVersion(u32),
+
+    /// Segment written with a previous format cannot be migrated.
+    #[error("cannot migrate segment: {0}")]
+    MigrationFailed(String),
+
+    /// Segment written with a previous format still has referenced objects.
+    #[error("cannot migrate segment: {0} objects are still referenced")]
+    ObjectsInUse(usize),
+
+    /// Header contains unknown allocation strategy.
+    #[error("invalid allocation strategy: {0}")]
+    InvalidAllocationStrategy(u8),
 
     /// Cannot attach SHM file or lock file.
     #[error("library failure: {0}")]
     FileSystemError(String),
 
+    /// Segment file already exists.
+    #[error("segment already exists")]
+    SegmentAlreadyExists,
+
     /// Error occurs in memory pool management.
     #[error("error with memory pool: {0}")]
     PoolError(#[from] ArrayPoolError),
+
+    /// Pool lock cannot be acquired in time.
+    #[error("timeout while waiting for pool lock")]
+    LockTimeout,
+
+    /// Awaited pool state has not been reached in time.
+    #[error("timeout while waiting for pool")]
+    WaitTimeout,
+
+    /// Segment creator died before initializing segment.
+    #[error("segment creator (process {0}) died during initialization")]
+    CreatorDied(u32),
+
+    /// Segment is smaller than size stored in header.
+    #[error("segment too small: {actual} bytes, expected at least {expected} bytes")]
+    SegmentTooSmall {
+        /// Size required by header.
+        expected: usize,
+        /// Size of mapping.
+        actual: usize,
+    },
+
+    /// Offsets and sizes stored in header do not describe a valid segment.
+    #[error("invalid segment layout")]
+    InvalidLayout,
+
+    /// Offset of data region stored in header does not match layout.
+    #[error("invalid data offset: {actual}, expected {expected}")]
+    InvalidDataOffset {
+        /// Offset computed from layout.
+        expected: u64,
+        /// Offset stored in header.
+        actual: u64,
+    },
+
+    /// Size of data region stored in header does not match layout.
+    #[error("invalid data size: {actual}, expected {expected}")]
+    InvalidDataSize {
+        /// Size computed from layout.
+        expected: u64,
+        /// Size stored in header.
+        actual: u64,
+    },
+
+    /// Offset of slot table stored in header does not match layout.
+    #[error("invalid slot table offset: {actual}, expected {expected}")]
+    InvalidSlotsOffset {
+        /// Offset computed from layout.
+        expected: u64,
+        /// Offset stored in header.
+        actual: u64,
+    },
+
+    /// Segment has been created by a process with another pointer width.
+    #[error("segment created with {actual}-byte pointers, expected {expected}-byte pointers")]
+    PointerWidthMismatch {
+        /// Pointer width of current process.
+        expected: u8,
+        /// Pointer width of creator.
+        actual: u8,
+    },
+
+    /// Layout stored in header does not match its checksum.
+    #[error("invalid segment layout checksum")]
+    InvalidLayoutChecksum,
 }
 
 impl From<ShmemError> for ShmError {
     fn from(err: ShmemError) -> Self {
-        Self::FileSystemError(err.to_string())
+        match err {
+            ShmemError::LinkExists => Self::SegmentAlreadyExists,
+            err => Self::FileSystemError(err.to_string()),
+        }
     }
 }
 
+/// Hash layout values with FNV-1a.
+fn layout_checksum(values: &[u64]) -> u64 {
+    values
+        .iter()
+        .flat_map(|x| x.to_le_bytes())
+        .fold(0xCBF2_9CE4_8422_2325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01B3)
+        })
+}
+
 /// Header of shm.
 ///
-/// Helps to check if module is correctly map to a valid shm segment.
+/// Helps to check if module is correctly map to a valid shm segment. Header
+/// describes segment layout, so it can be checked against mapping before use.
+///
+/// Header only contains fixed width fields and explicit padding, so its layout
+/// does not depend on target or compiler (see `doc/SEGMENT_FORMAT.md`). Magic
+/// value and version are stored first in every version of the format.
 #[derive(Debug)]
 #[repr(C)]
 pub struct ShmHeader {
     magic: u64,
-    version: u8,
-    slot_count: usize,
-    spin_lock: SimpleSpinLock,
+    version: u32,
+    /// Pointer width of creator process.
+    pointer_width: u8,
+    strategy: u8,
+    flags: u8,
+    _padding0: u8,
+    creator_pid: AtomicU32,
+    state: AtomicU32,
+    created_at: u64,
+    segment_size: u64,
+    data_offset: u64,
+    data_size: AtomicU64,
+    slots_offset: AtomicU64,
+    slot_count: AtomicU64,
+    layout_checksum: AtomicU64,
+    segment_count: AtomicU64,
+    max_segment_count: u64,
+    name_count: u64,
+    alignment: u64,
+    next_python_id: AtomicU64,
+    next_generation: AtomicU64,
+    lock: RobustLock,
+    published: Event,
+    released: Event,
 }
 
 impl ShmHeader {
     /// Create new header.
-    pub const fn new(slot_count: usize) -> Self {
-        Self {
+    pub fn new(
+        slot_count: usize,
+        segment_size: usize,
+        alignment: usize,
+        strategy: AllocationStrategy,
+    ) -> Self {
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |x| x.as_secs());
+
+        let header = Self {
             magic: SHM_HEADER_MAGIC,
             version: SHM_VERSION,
-            slot_count,
-            spin_lock: SimpleSpinLock::new(),
-        }
+            pointer_width: POINTER_WIDTH,
+            strategy: strategy.as_raw(),
+            flags: 0,
+            _padding0: 0,
+            creator_pid: AtomicU32::new(std::process::id()),
+            state: AtomicU32::new(STATE_INITIALIZING),
+            created_at,
+            segment_size: segment_size as u64,
+            data_offset: SHM_HEADER_SIZE as u64,
+            data_size: AtomicU64::new(0),
+            slots_offset: AtomicU64::new(0),
+            slot_count: AtomicU64::new(0),
+            layout_checksum: AtomicU64::new(0),
+            segment_count: AtomicU64::new(1),
+            max_segment_count: 1,
+            name_count: 0,
+            alignment: alignment as u64,
+            next_python_id: AtomicU64::new(FIRST_POOL_PYTHON_ID),
+            next_generation: AtomicU64::new(1),
+            lock: RobustLock::new(),
+            published: Event::new(),
+            released: Event::new(),
+        };
+        header.set_slot_count(slot_count);
+        header
     }
 
-    /// Check header contains valid data.
-    pub const fn valid(&self) -> Result<(), ShmError> {
+    /// Check header has been written by a compatible process.
+    ///
+    /// Only fields which never change are checked, so lock is not required.
+    pub const fn check_format(&self) -> Result<(), ShmError> {
         if self.magic != SHM_HEADER_MAGIC {
             Err(ShmError::InvalidShmMagicValue)
         } else if self.version != SHM_VERSION {
-            Err(ShmError::InvalidShmVersion)
+            Err(ShmError::UnsupportedShmVersion(self.version))
+        } else if self.pointer_width != POINTER_WIDTH {
+            Err(ShmError::PointerWidthMismatch {
+                expected: POINTER_WIDTH,
+                actual: self.pointer_width,
+            })
+        } else if AllocationStrategy::from_raw(self.strategy).is_none() {
+            Err(ShmError::InvalidAllocationStrategy(self.strategy))
         } else {
             Ok(())
         }
     }
 
+    /// Check header contains valid data and describes a segment of `segment_len` bytes.
+    ///
+    /// Layout can change when slot table grows, so lock must be held.
+    pub fn valid(&self, segment_len: usize) -> Result<(), ShmError> {
+        self.check_format()?;
+
+        if self.segment_size > segment_len as u64 {
+            return Err(ShmError::SegmentTooSmall {
+                expected: self.segment_size(),
+                actual: segment_len,
+            });
+        }
+        if self.layout_checksum.load(Ordering::Acquire) != self.compute_layout_checksum() {
+            return Err(ShmError::InvalidLayoutChecksum);
+        }
+
+        // Bound counts first, so computing layout cannot overflow
+        let segment_size = self.segment_size();
+        let slot_count = self.slot_count.load(Ordering::Acquire);
+        let fits = slot_count <= (segment_size / MEMORY_SLOT_SIZE) as u64
+            && self.name_count <= (segment_size / NAME_ENTRY_SIZE) as u64
+            && SegmentLayout::segment_size(self.slot_count(), self.name_count(), 0) <= segment_size;
+        if !fits {
+            return Err(ShmError::InvalidLayout);
+        }
+
+        let layout = self.layout();
+        if self.data_offset != layout.data_offset as u64 {
+            return Err(ShmError::InvalidDataOffset {
+                expected: layout.data_offset as u64,
+                actual: self.data_offset,
+            });
+        }
+        let data_size = self.data_size.load(Ordering::Acquire);
+        if data_size != layout.data_size as u64 {
+            return Err(ShmError::InvalidDataSize {
+                expected: layout.data_size as u64,
+                actual: data_size,
+            });
+        }
+        let slots_offset = self.slots_offset.load(Ordering::Acquire);
+        if slots_offset != layout.slots_offset as u64 {
+            return Err(ShmError::InvalidSlotsOffset {
+                expected: layout.slots_offset as u64,
+                actual: slots_offset,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Get checksum of layout fields.
+    ///
+    /// Size of tables entries is included, so a segment created with other
+    /// struct definitions is rejected.
+    fn compute_layout_checksum(&self) -> u64 {
+        layout_checksum(&[
+            self.segment_size,
+            self.name_count,
+            self.slot_count.load(Ordering::Acquire),
+            self.data_offset,
+            self.data_size.load(Ordering::Acquire),
+            self.slots_offset.load(Ordering::Acquire),
+            SHM_HEADER_SIZE as u64,
+            MEMORY_SLOT_SIZE as u64,
+            INDEX_ENTRY_SIZE as u64,
+            NAME_ENTRY_SIZE as u64,
+        ])
+    }
+
+    /// Set slot count and store resulting layout (and its checksum).
+    ///
+    /// Lock must be held, unless segment is not shared yet.
+    fn set_slot_count(&self, slot_count: usize) {
+        let layout = SegmentLayout::new(slot_count, self.name_count(), self.segment_size());
+        self.data_size
+            .store(layout.data_size as u64, Ordering::Release);
+        self.slots_offset
+            .store(layout.slots_offset as u64, Ordering::Release);
+        self.slot_count.store(slot_count as u64, Ordering::Release);
+        self.layout_checksum
+            .store(self.compute_layout_checksum(), Ordering::Release);
+    }
+
+    /// Mark segment as initialized and wake up processes waiting to open it.
+    fn set_ready(&self) {
+        self.state.store(STATE_READY, Ordering::Release);
+        futex_wake(&self.state, u32::MAX);
+    }
+
+    /// Get ID of process which created segment.
+    pub fn creator_pid(&self) -> u32 {
+        self.creator_pid.load(Ordering::Acquire)
+    }
+
+    /// Get segment creation time.
+    pub fn created_at(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.created_at)
+    }
+
+    /// Get allocation strategy shared by all processes.
+    pub fn strategy(&self) -> AllocationStrategy {
+        AllocationStrategy::from_raw(self.strategy).unwrap_or_default()
+    }
+
+    /// Get size of segment in bytes.
+    pub const fn segment_size(&self) -> usize {
+        self.segment_size as usize
+    }
+
+    /// Get default alignment of objects data.
+    pub const fn alignment(&self) -> usize {
+        self.alignment as usize
+    }
+
+    /// Get maximum number of segments used by pool.
+    pub const fn max_segment_count(&self) -> usize {
+        self.max_segment_count as usize
+    }
+
+    /// Get maximum number of named objects.
+    pub const fn name_count(&self) -> usize {
+        self.name_count as usize
+    }
+
+    /// Get current slot count.
+    ///
+    /// Slot table can grow, so value must be read when lock is held.
+    pub fn slot_count(&self) -> usize {
+        self.slot_count.load(Ordering::Acquire) as usize
+    }
+
+    /// Get layout of first segment.
+    fn layout(&self) -> SegmentLayout {
+        SegmentLayout::new(self.slot_count(), self.name_count(), self.segment_size())
+    }
+
+    /// Get number of segments used by pool.
+    ///
+    /// Segments can be added, so value must be read when lock is held.
+    pub fn segment_count(&self) -> usize {
+        self.segment_count.load(Ordering::Acquire) as usize
+    }
+
+    /// Check if pool must be compacted when an allocation fails.
+    pub const fn auto_compact(&self) -> bool {
+        self.flags & FLAG_HEADER_AUTO_COMPACT == FLAG_HEADER_AUTO_COMPACT
+    }
+
     /// Acquire memory lock
-    pub fn lock(&self) -> SimpleSpinLockGuard<'_> {
-        self.spin_lock.lock()
+    pub fn lock(&self) -> RobustLockGuard<'_> {
+        self.lock.lock()
+    }
+
+    /// Acquire memory lock, giving up after `timeout`.
+    pub fn try_lock_for(&self, timeout: Duration) -> Result<RobustLockGuard<'_>, ShmError> {
+        self.lock.try_lock_for(timeout).ok_or(ShmError::LockTimeout)
+    }
+}
+
+/// Get path of additional segment of a pool.
+fn segment_file_path(segment_path: &Path, segment: usize) -> PathBuf {
+    let mut path = OsString::from(segment_path);
+    path.push(format!(".{segment}"));
+    path.into()
+}
+
+/// Mapped segment, which can be shared between threads.
+struct Segment(Shmem);
+
+// Mapping is never moved nor resized once created, and data it contains is
+// only modified under pool lock (or through object data given to callers).
+unsafe impl Send for Segment {}
+unsafe impl Sync for Segment {}
+
+impl Deref for Segment {
+    type Target = Shmem;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Segment {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// Data of an object stored in pool.
+///
+/// Data is shared with other threads and processes (which may hold views of
+/// same object), so pool never hands out slices: they are only borrowed through
+/// `unsafe` methods, whose caller must synchronize accesses to object.
+#[derive(Debug)]
+pub struct ObjectData<'a> {
+    ptr: *mut u8,
+    len: usize,
+    _marker: PhantomData<&'a [u8]>,
+}
+
+// View cannot be used to access data without `unsafe`
+unsafe impl Send for ObjectData<'_> {}
+unsafe impl Sync for ObjectData<'_> {}
+
+impl<'a> ObjectData<'a> {
+    /// Get size of object data.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check if object data is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get pointer to start of object data.
+    pub fn as_ptr(&self) -> *mut u8 {
+        self.ptr
+    }
+
+    /// Borrow object data.
+    ///
+    /// # Safety
+    ///
+    /// Data must not be written (by any thread or process) while slice is used,
+    /// and object must not be moved nor released meanwhile.
+    pub unsafe fn as_slice(&self) -> &'a [u8] {
+        std::slice::from_raw_parts(self.ptr, self.len)
+    }
+
+    /// Mutably borrow object data.
+    ///
+    /// # Safety
+    ///
+    /// Data must not be accessed by anyone else (including through another view
+    /// of same object) while slice is used, and object must not be moved nor
+    /// released meanwhile.
+    pub unsafe fn as_mut_slice(&self) -> &'a mut [u8] {
+        std::slice::from_raw_parts_mut(self.ptr, self.len)
     }
 }
 
 /// Shm bind memory object pool.
+///
+/// Pool data can be spread over multiple segments. First segment contains
+/// header, slot table and data. Additional segments only contains data (and a
+/// copy of header to check they are valid) and are mapped on first use.
+///
+/// Pool can be shared between threads: memory pool is only used while holding
+/// both pool lock (shared by all processes) and a lock local to this instance.
 pub struct ShmObjectPool<'a> {
-    shmem: Shmem,
+    shmem: Segment,
+    segment_path: PathBuf,
+    segments: Mutex<Vec<Segment>>,
     header: &'a ShmHeader,
-    memory_pool: RefCell<MemoryPool<'a>>,
+    memory_pool: Mutex<MemoryPool<'a>>,
     offset_data: usize,
-    _marker: PhantomData<&'a Shmem>,
+    lock_timeout: Duration,
+    _marker: PhantomData<&'a Segment>,
+}
+
+impl Drop for ShmObjectPool<'_> {
+    fn drop(&mut self) {
+        if !self.shmem.is_owner() {
+            return;
+        }
+
+        // Segments added by other processes may never have been mapped
+        let mapped = self
+            .segments
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len();
+        for segment in mapped + 1..self.header.segment_count() {
+            if let Ok(mut shmem) = ShmemConf::new()
+                .flink(segment_file_path(&self.segment_path, segment))
+                .open()
+            {
+                shmem.set_owner(true);
+            }
+        }
+    }
 }
 
 impl<'a> ShmObjectPool<'a> {
     /// Create struct reading existing shm.
+    ///
+    /// If segment is being created by another process, waits for its
+    /// initialization to complete.
     pub fn open<P>(segment_path: P) -> Result<Self, ShmError>
     where
         P: AsRef<Path>,
     {
+        Self::open_with_timeout(segment_path.as_ref(), DEFAULT_LOCK_TIMEOUT)
+    }
+
+    fn open_with_timeout(segment_path: &Path, lock_timeout: Duration) -> Result<Self, ShmError> {
         // Open SHM and lockfile
-        let segment_path = segment_path.as_ref();
         let shmem = ShmemConf::new().flink(segment_path).open()?;
 
         let raw_ptr = shmem.as_ptr();
+        wait_initialized(&shmem, lock_timeout)?;
 
-        // Read and check header
-        let header = unsafe { &*(raw_ptr as *const ShmHeader) };
-        header.valid()?;
+        // Migrate segment written with previous format
+        if header_magic(&shmem) == Some(migration::LEGACY_HEADER_MAGIC) {
+            migration::migrate(&shmem, segment_path, lock_timeout)?;
+        }
 
-        // Read slots array
-        let slots = unsafe {
-            std::slice::from_raw_parts_mut(
-                raw_ptr.add(SHM_HEADER_SIZE) as *mut MemorySlot,
-                header.slot_count,
-            )
+        // Read and check header
+        let header = read_header(&shmem)?;
+        header.check_format()?;
+
+        // Read slots and index arrays
+        let memory_pool = {
+            let _guard = header.try_lock_for(lock_timeout)?;
+            header.valid(shmem.len())?;
+            unsafe { map_memory_pool(raw_ptr, header) }
         };
 
         // Create struct
         Ok(ShmObjectPool {
-            shmem,
+            shmem: Segment(shmem),
+            segment_path: segment_path.into(),
+            segments: Mutex::new(Vec::new()),
             header,
-            memory_pool: RefCell::new(MemoryPool::new(slots)),
-            offset_data: SHM_HEADER_SIZE + header.slot_count * MEMORY_SLOT_SIZE,
+            memory_pool: Mutex::new(memory_pool),
+            offset_data: SHM_HEADER_SIZE,
+            lock_timeout,
             _marker: PhantomData,
         })
     }
 
+    /// Set maximum duration to wait for pool lock.
+    ///
+    /// Operations fail with [`ShmError::LockTimeout`] once it expires.
+    pub fn with_lock_timeout(mut self, timeout: Duration) -> Self {
+        self.lock_timeout = timeout;
+        self
+    }
+
+    /// Get default alignment of objects data.
+    pub fn alignment(&self) -> usize {
+        self.header.alignment()
+    }
+
     /// Add object to shm.
     pub fn add_object(
         &self,
         python_id: PythonId,
         request_size: usize,
-    ) -> Result<&'_ mut [u8], ShmError> {
-        let _guard = self.header.lock();
-        let offset = self
-            .memory_pool
-            .borrow_mut()
-            .add_object(python_id, request_size)?;
-
-        let obj_mem_info = ObjectInfo::new(offset, request_size);
-        Ok(self.slice_mut_from(obj_mem_info))
+    ) -> Result<ObjectData<'_>, ShmError> {
+        self.add_object_aligned(python_id, request_size, self.header.alignment())
     }
 
-    /// Mark object as used by current process.
-    pub fn attach_object(&self, python_id: PythonId) -> Result<&'_ mut [u8], ShmError> {
-        let _guard = self.header.lock();
-        let obj_mem_info = self.memory_pool.borrow_mut().attach_object(python_id)?;
-        Ok(self.slice_mut_from(obj_mem_info))
+    /// Add object to shm, waiting for memory to be released if pool is full.
+    ///
+    /// Fails with [`ShmError::WaitTimeout`] if there is still not enough space
+    /// once `timeout` expires.
+    pub fn add_object_blocking(
+        &self,
+        python_id: PythonId,
+        request_size: usize,
+        timeout: Duration,
+    ) -> Result<ObjectData<'_>, ShmError> {
+        let deadline = Instant::now().checked_add(timeout);
+
+        loop {
+            let sequence = self.header.released.sequence();
+            match self.add_object(python_id, request_size) {
+                Err(ShmError::PoolError(
+                    ArrayPoolError::NoSpaceLeft | ArrayPoolError::NoFreeBlocLeft,
+                )) => {}
+                result => return result,
+            }
+
+            let remaining =
+                deadline.map_or(timeout, |x| x.saturating_duration_since(Instant::now()));
+            if remaining.is_zero() {
+                return Err(ShmError::WaitTimeout);
+            }
+            self.header.released.wait(sequence, remaining);
+        }
     }
 
-    /// Un-mark object as used by current process.
-    pub fn detach_object(&self, python_id: PythonId) -> Result<(), ShmError> {
-        let _guard = self.header.lock();
-        self.memory_pool.borrow_mut().detach_object(python_id)?;
-        Ok(())
+    /// Add object to shm with a specific data alignment.
+    pub fn add_object_aligned(
+        &self,
+        python_id: PythonId,
+        request_size: usize,
+        alignment: usize,
+    ) -> Result<ObjectData<'_>, ShmError> {
+        let guard = self.lock()?;
+        let mut memory_pool = self.memory_pool(&guard);
+        self.add_locked(&mut memory_pool, python_id, request_size, alignment, None)
     }
 
-    /// Set object as releasable from pool and hijack GC.
-    pub fn set_object_releasable(&self, python_id: PythonId) -> Result<(), ShmError> {
-        let _guard = self.header.lock();
-        self.memory_pool
-            .borrow_mut()
-            .set_object_releasable(python_id)?;
-        Ok(())
+    /// Add object to shm and store its array descriptor.
+    pub fn add_described_object(
+        &self,
+        python_id: PythonId,
+        request_size: usize,
+        descriptor: &ArrayDescriptor,
+    ) -> Result<ObjectData<'_>, ShmError> {
+        let alignment = self.header.alignment();
+        let guard = self.lock()?;
+        let mut memory_pool = self.memory_pool(&guard);
+        self.add_locked(
+            &mut memory_pool,
+            python_id,
+            request_size,
+            alignment,
+            Some(descriptor),
+        )
     }
 
-    /// Get memory offset of given object.
-    pub fn slice_of(&self, python_id: PythonId) -> Option<&'_ mut [u8]> {
-        let _guard = self.header.lock();
-        let obj_mem_info = self.memory_pool.borrow().info_of(python_id)?;
-        Some(self.slice_mut_from(obj_mem_info))
+    /// Add object to shm using a new python ID issued by pool.
+    ///
+    /// Issued IDs are never used twice by a pool, so they cannot alias another object.
+    pub fn allocate(
+        &self,
+        request_size: usize,
+    ) -> Result<(ObjectHandle, ObjectData<'_>), ShmError> {
+        self.allocate_with(request_size, self.header.alignment(), None)
     }
 
-    /// Dump memory info to stdout.
-    pub fn dump(&self) -> String {
-        let _guard = self.header.lock();
-        self.memory_pool.borrow().dump()
+    /// Add object to shm with a specific data alignment, using a new python ID issued by pool.
+    pub fn allocate_aligned(
+        &self,
+        request_size: usize,
+        alignment: usize,
+    ) -> Result<(ObjectHandle, ObjectData<'_>), ShmError> {
+        self.allocate_with(request_size, alignment, None)
     }
 
-    fn slice_mut_from(&self, obj_mem_info: ObjectInfo) -> &'_ mut [u8] {
-        let data_offset = obj_mem_info.offset() + self.offset_data;
-
-        unsafe {
-            std::slice::from_raw_parts_mut(
-                self.shmem.as_ptr().add(data_offset),
-                obj_mem_info.size(),
-            )
-        }
+    /// Add object to shm with its array descriptor, using a new python ID issued by pool.
+    pub fn allocate_described(
+        &self,
+        request_size: usize,
+        descriptor: &ArrayDescriptor,
+    ) -> Result<(ObjectHandle, ObjectData<'_>), ShmError> {
+        self.allocate_with(request_size, self.header.alignment(), Some(descriptor))
     }
-}
 
-impl<'a> fmt::Debug for ShmObjectPool<'a> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("ShmObjectPool").finish()
+    fn allocate_with(
+        &self,
+        request_size: usize,
+        alignment: usize,
+        descriptor: Option<&ArrayDescriptor>,
+    ) -> Result<(ObjectHandle, ObjectData<'_>), ShmError> {
+        let guard = self.lock()?;
+        let mut memory_pool = self.memory_pool(&guard);
+
+        let python_id = memory_pool.mint_python_id(&self.header.next_python_id);
+        let slice = self.add_locked(
+            &mut memory_pool,
+            python_id,
+            request_size,
+            alignment,
+            descriptor,
+        )?;
+        Ok((memory_pool.handle_of(python_id)?, slice))
     }
-}
 
-/// Helper to create new shm object pool.
-#[derive(Debug)]
-pub struct ShmObjectPoolBuilder {
-    slot_count: usize,
-    data_size: usize,
-    segment_path: PathBuf,
-}
+    /// Add object to shm and register it with given name.
+    ///
+    /// Object uses a python ID issued by pool. Name is removed from pool once
+    /// object has been released.
+    pub fn add_named(
+        &self,
+        name: &str,
+        request_size: usize,
+    ) -> Result<(ObjectHandle, ObjectData<'_>), ShmError> {
+        check_name(name)?;
 
-impl ShmObjectPoolBuilder {
-    /// Create default pool configuration.
-    pub fn new() -> Self {
-        Self {
-            slot_count: 10_000,
-            data_size: 512 * 1024 * 1024,
-            segment_path: "/dev/shm/obj_pool.seg".into(),
+        let alignment = self.header.alignment();
+        let guard = self.lock()?;
+        let mut memory_pool = self.memory_pool(&guard);
+
+        if self.name_table(&memory_pool).get(name).is_some() {
+            return Err(ArrayPoolError::NameAlreadyExists.into());
         }
+
+        let python_id = memory_pool.mint_python_id(&self.header.next_python_id);
+        let slice = self.add_locked(&mut memory_pool, python_id, request_size, alignment, None)?;
+        let handle = memory_pool.handle_of(python_id)?;
+
+        // Slot table may have been moved, so name table is mapped again
+        if let Err(err) = self.name_table(&memory_pool).insert(name, handle) {
+            memory_pool.remove_object(python_id)?;
+            return Err(err.into());
+        }
+
+        Ok((handle, slice))
     }
 
-    /// Set pool slot count.
-    pub fn slot_count(mut self, value: usize) -> Self {
-        self.slot_count = value;
-        self
+    /// Mark object registered with given name as used by current process.
+    pub fn attach_named(&self, name: &str) -> Result<(ObjectHandle, ObjectData<'_>), ShmError> {
+        let guard = self.lock()?;
+        let mut memory_pool = self.memory_pool(&guard);
+
+        let handle = self
+            .name_table(&memory_pool)
+            .get(name)
+            .ok_or(ArrayPoolError::NameNotFound)?;
+        let obj_mem_info = memory_pool.attach_object(handle)?;
+        Ok((handle, self.data_from(obj_mem_info)?))
     }
 
-    /// Set pool data size.
-    pub fn data_size(mut self, value: usize) -> Self {
-        self.data_size = value;
-        self
+    /// Get handle of object registered with given name.
+    pub fn lookup(&self, name: &str) -> Result<Option<ObjectHandle>, ShmError> {
+        let guard = self.lock()?;
+        let memory_pool = self.memory_pool(&guard);
+        Ok(self.name_table(&memory_pool).get(name))
     }
 
-    /// Set pool file path.
-    pub fn segment_path<P>(mut self, value: P) -> Self
-    where
-        P: AsRef<Path>,
-    {
-        self.segment_path = value.as_ref().into();
-        self
+    /// Get names of every named object.
+    pub fn names(&self) -> Result<Vec<String>, ShmError> {
+        let guard = self.lock()?;
+        let memory_pool = self.memory_pool(&guard);
+        Ok(self
+            .name_table(&memory_pool)
+            .iter()
+            .map(|(name, _)| name.to_owned())
+            .collect())
     }
 
-    /// Create pool with current configuration.
-    pub fn create<'a>(&self) -> Result<ShmObjectPool<'a>, ShmError> {
-        let size = SHM_HEADER_SIZE + self.slot_count * MEMORY_SLOT_SIZE + self.data_size;
+    /// Get name table, removing names of objects released from pool.
+    ///
+    /// Memory pool is required to prove lock is held.
+    fn name_table(&self, memory_pool: &MemoryPool<'a>) -> NameTable<'_> {
+        let entries = unsafe { self.header.layout().names(self.shmem.as_ptr()) };
+        let mut name_table = NameTable::new(entries);
+        name_table.retain(|handle| memory_pool.check_handle(handle).is_ok());
+        name_table
+    }
 
-        // Open segment
-        let shmem = ShmemConf::new()
-            .size(size)
-            .flink(&self.segment_path)
-            .create()?;
+    /// Add object (and its array descriptor if any) while lock is held.
+    fn add_locked(
+        &self,
+        memory_pool: &mut MemoryPool<'a>,
+        python_id: PythonId,
+        request_size: usize,
+        alignment: usize,
+        descriptor: Option<&ArrayDescriptor>,
+    ) -> Result<ObjectData<'_>, ShmError> {
+        let prefix_size = descriptor.map_or(0, |_| DESCRIPTOR_SIZE);
+        self.allocate_locked(
+            memory_pool,
+            prefix_size + request_size + alignment,
+            |memory_pool| {
+                match descriptor {
+                    Some(_) => {
+                        memory_pool.add_described_object(python_id, request_size, alignment)?
+                    }
+                    None => memory_pool.add_object_aligned(python_id, request_size, alignment)?,
+                };
+                Ok(())
+            },
+        )?;
+
+        if let Some(descriptor) = descriptor {
+            let descriptor_info = memory_pool
+                .descriptor_info_of(python_id)?
+                .ok_or(ArrayPoolError::InvalidDescriptor)?;
+            let descriptor_data = self.data_from(descriptor_info)?;
+            unsafe {
+                std::ptr::write_unaligned(
+                    descriptor_data.as_ptr() as *mut ArrayDescriptor,
+                    *descriptor,
+                );
+            }
+        }
 
-        let raw_ptr = shmem.as_ptr();
+        let obj_mem_info = memory_pool
+            .info_of(python_id)
+            .ok_or(ArrayPoolError::ObjectNotFound(python_id))?;
+        let slice = self.data_from(obj_mem_info)?;
 
-        // Init header
-        let header = unsafe { &mut *(raw_ptr as *mut ShmHeader) };
-        *header = ShmHeader::new(self.slot_count);
+        self.header.published.notify_all();
+        Ok(slice)
+    }
 
-        // Create object pool
-        let slots = unsafe {
-            std::slice::from_raw_parts_mut(
-                raw_ptr.add(SHM_HEADER_SIZE) as *mut MemorySlot,
-                header.slot_count,
-            )
+    /// Get array descriptor of given object.
+    ///
+    /// Returns `None` if object has been added without descriptor.
+    pub fn describe(&self, python_id: PythonId) -> Result<Option<ArrayDescriptor>, ShmError> {
+        let guard = self.lock()?;
+        let descriptor_info = match self.memory_pool(&guard).descriptor_info_of(python_id)? {
+            Some(descriptor_info) => descriptor_info,
+            None => return Ok(None),
         };
 
-        Ok(ShmObjectPool {
-            shmem,
-            header,
-            memory_pool: RefCell::new(MemoryPool::from_uninit_slice(slots, self.data_size)),
-            offset_data: SHM_HEADER_SIZE + header.slot_count * MEMORY_SLOT_SIZE,
-            _marker: PhantomData,
-        })
-    }
-}
+        let descriptor_data = self.data_from(descriptor_info)?;
+        let descriptor =
+            unsafe { std::ptr::read_unaligned(descriptor_data.as_ptr() as *const ArrayDescriptor) };
+        descriptor.valid()?;
 
-impl Default for ShmObjectPoolBuilder {
-    fn default() -> Self {
-        Self::new()
+        Ok(Some(descriptor))
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    mod shm_header {
-        use super::*;
 
-        #[test]
-        fn test_valid() {
-            let mut header = ShmHeader::new(10);
-            assert_eq!(header.valid(), Ok(()));
+    /// Add several objects to shm while holding lock once.
+    ///
+    /// If an object cannot be added, objects previously added by this call are
+    /// removed and error is returned.
+    pub fn add_objects(
+        &self,
+        objects: &[(PythonId, usize)],
+    ) -> Result<Vec<ObjectData<'_>>, ShmError> {
+        let alignment = self.header.alignment();
+        let guard = self.lock()?;
+        let mut memory_pool = self.memory_pool(&guard);
+
+        let mut slices = Vec::with_capacity(objects.len());
+        for (idx, (python_id, request_size)) in objects.iter().copied().enumerate() {
+            match self.add_locked(&mut memory_pool, python_id, request_size, alignment, None) {
+                Ok(slice) => slices.push(slice),
+                Err(err) => {
+                    // Best effort: every added object is removed, whatever fails
+                    for (python_id, _) in &objects[..idx] {
+                        let _ = memory_pool.remove_object(*python_id);
+                    }
+                    return Err(err);
+                }
+            }
+        }
 
-            header.version = SHM_VERSION + 1;
-            assert_eq!(header.valid(), Err(ShmError::InvalidShmVersion));
+        Ok(slices)
+    }
 
-            header.magic = 0x00;
-            assert_eq!(header.valid(), Err(ShmError::InvalidShmMagicValue));
+    /// Mark several objects as used by current process while holding lock once.
+    ///
+    /// No object is attached if one of them cannot be found or mapped.
+    pub fn attach_objects<H>(&self, handles: &[H]) -> Result<Vec<ObjectData<'_>>, ShmError>
+    where
+        H: Into<ObjectHandle> + Copy,
+    {
+        let guard = self.lock()?;
+        let mut memory_pool = self.memory_pool(&guard);
+
+        // Every object is resolved before any reference count is changed
+        let slices = handles
+            .iter()
+            .map(|handle| {
+                let obj_mem_info = memory_pool.handle_info_of(*handle)?;
+                self.data_from(obj_mem_info)
+            })
+            .collect::<Result<Vec<_>, ShmError>>()?;
+
+        for handle in handles {
+            memory_pool.attach_object(*handle)?;
         }
+        Ok(slices)
     }
 
-    mod shm_object_pool {
+    /// Un-mark several objects as used by current process while holding lock once.
+    ///
+    /// No object is detached if one of them cannot be found or is listed
+    /// several times.
+    pub fn detach_objects<H>(&self, handles: &[H]) -> Result<(), ShmError>
+    where
+        H: Into<ObjectHandle> + Copy,
+    {
+        let guard = self.lock()?;
+        let mut memory_pool = self.memory_pool(&guard);
+
+        // Detaching an object may release it, so it cannot be detached twice
+        let mut python_ids = Vec::with_capacity(handles.len());
+        for handle in handles {
+            memory_pool.check_handle(*handle)?;
+            python_ids.push((*handle).into().python_id().0);
+        }
+        python_ids.sort_unstable();
+        if let Some(python_id) = python_ids.windows(2).find(|x| x[0] == x[1]) {
+            return Err(ArrayPoolError::DuplicateHandle(PythonId(python_id[0])).into());
+        }
+
+        for handle in handles {
+            memory_pool.detach_object(*handle)?;
+        }
+        Ok(())
+    }
+
+    /// Change size of an existing object.
+    ///
+    /// Object is moved if it cannot be resized in place, in which case data
+    /// previously returned for it (in any process) must not be used anymore.
+    pub fn resize_object(
+        &self,
+        python_id: PythonId,
+        new_size: usize,
+    ) -> Result<ObjectData<'_>, ShmError> {
+        let guard = self.lock()?;
+        let mut memory_pool = self.memory_pool(&guard);
+        let prefix_size = memory_pool
+            .descriptor_info_of(python_id)?
+            .map_or(0, |x| x.size());
+
+        let obj_mem_info = self.allocate_locked(
+            &mut memory_pool,
+            prefix_size + new_size + self.header.alignment(),
+            |memory_pool| {
+                let data_ptrs = self.data_ptrs()?;
+                let obj_mem_info =
+                    memory_pool.resize_object(python_id, new_size, |from, to| unsafe {
+                        std::ptr::copy_nonoverlapping(
+                            data_ptrs[from.segment()].add(from.offset()),
+                            data_ptrs[to.segment()].add(to.offset()),
+                            from.size(),
+                        );
+                    })?;
+                Ok(obj_mem_info)
+            },
+        )?;
+
+        self.data_from(obj_mem_info)
+    }
+
+    /// Run an operation allocating memory, making room for it if pool is full.
+    ///
+    /// Pool is compacted (if auto compaction is enabled), a segment able to
+    /// store `min_data_size` bytes is added or slot table is grown, depending on
+    /// what operation is missing.
+    fn allocate_locked<T, F>(
+        &self,
+        memory_pool: &mut MemoryPool<'a>,
+        min_data_size: usize,
+        mut operation: F,
+    ) -> Result<T, ShmError>
+    where
+        F: FnMut(&mut MemoryPool<'a>) -> Result<T, ShmError>,
+    {
+        let mut compacted = false;
+        loop {
+            match operation(memory_pool) {
+                Err(ShmError::PoolError(ArrayPoolError::NoSpaceLeft))
+                    if self.header.auto_compact() && !compacted =>
+                {
+                    self.compact_locked(memory_pool)?;
+                    compacted = true;
+                }
+                Err(ShmError::PoolError(ArrayPoolError::NoSpaceLeft))
+                    if self.header.segment_count() < self.header.max_segment_count() =>
+                {
+                    self.add_segment(memory_pool, min_data_size)?;
+                }
+                Err(ShmError::PoolError(ArrayPoolError::NoFreeBlocLeft)) => {
+                    if self.grow_slot_table(memory_pool).is_err() {
+                        if !self.header.auto_compact() || compacted {
+                            return Err(ArrayPoolError::NoFreeBlocLeft.into());
+                        }
+                        self.compact_locked(memory_pool)?;
+                        compacted = true;
+                    }
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Mark object as used by current process.
+    ///
+    /// Object can be referenced by python ID or by handle, in which case
+    /// generation is checked.
+    pub fn attach_object<H>(&self, handle: H) -> Result<ObjectData<'_>, ShmError>
+    where
+        H: Into<ObjectHandle>,
+    {
+        let guard = self.lock()?;
+        let obj_mem_info = self.memory_pool(&guard).attach_object(handle)?;
+        self.data_from(obj_mem_info)
+    }
+
+    /// Mark object as used by current process, waiting for it to be added to pool.
+    ///
+    /// Fails with [`ShmError::WaitTimeout`] if object is still missing once
+    /// `timeout` expires.
+    pub fn attach_object_wait<H>(
+        &self,
+        handle: H,
+        timeout: Duration,
+    ) -> Result<ObjectData<'_>, ShmError>
+    where
+        H: Into<ObjectHandle>,
+    {
+        let handle = handle.into();
+        let deadline = Instant::now().checked_add(timeout);
+
+        loop {
+            let sequence = self.header.published.sequence();
+            match self.attach_object(handle) {
+                Err(ShmError::PoolError(ArrayPoolError::ObjectNotFound(_))) => {}
+                result => return result,
+            }
+
+            let remaining =
+                deadline.map_or(timeout, |x| x.saturating_duration_since(Instant::now()));
+            if remaining.is_zero() {
+                return Err(ShmError::WaitTimeout);
+            }
+            self.header.published.wait(sequence, remaining);
+        }
+    }
+
+    /// Un-mark object as used by current process.
+    pub fn detach_object<H>(&self, handle: H) -> Result<(), ShmError>
+    where
+        H: Into<ObjectHandle>,
+    {
+        let guard = self.lock()?;
+        self.memory_pool(&guard).detach_object(handle)?;
+        Ok(())
+    }
+
+    /// Set object as releasable from pool and hijack GC.
+    pub fn set_object_releasable<H>(&self, handle: H) -> Result<(), ShmError>
+    where
+        H: Into<ObjectHandle>,
+    {
+        let guard = self.lock()?;
+        self.memory_pool(&guard).set_object_releasable(handle)?;
+        Ok(())
+    }
+
+    /// Get handle (python ID and generation) of given object.
+    pub fn handle_of(&self, python_id: PythonId) -> Result<ObjectHandle, ShmError> {
+        let guard = self.lock()?;
+        Ok(self.memory_pool(&guard).handle_of(python_id)?)
+    }
+
+    /// Get memory offset of given object.
+    ///
+    /// No reference is taken, so object is pinned: compaction never moves it.
+    pub fn slice_of(&self, python_id: PythonId) -> Option<ObjectData<'_>> {
+        let guard = self.lock().ok()?;
+        let obj_mem_info = self.memory_pool(&guard).pinned_info_of(python_id)?;
+        self.data_from(obj_mem_info).ok()
+    }
+
+    /// Move unused objects toward start of data region to merge free blocks.
+    ///
+    /// Objects not attached by any process may be moved, unless their data has been
+    /// returned by [`ShmObjectPool::slice_of`].
+    ///
+    /// Returns how many bytes have been added to largest free block.
+    pub fn compact(&self) -> Result<usize, ShmError> {
+        let guard = self.lock()?;
+        self.compact_locked(&mut self.memory_pool(&guard))
+    }
+
+    /// Acquire pool lock.
+    ///
+    /// Fails with [`ShmError::LockTimeout`] if lock cannot be acquired within
+    /// pool lock timeout. If a process died while holding lock, slot table is
+    /// checked before being used again.
+    fn lock(&self) -> Result<RobustLockGuard<'_>, ShmError> {
+        let guard = self.header.try_lock_for(self.lock_timeout)?;
+        if guard.owner_died() {
+            self.memory_pool(&guard).check_consistency()?;
+            guard.mark_consistent();
+        }
+        Ok(guard)
+    }
+
+    /// Get memory pool, mapping it again if slot table has been moved by another process.
+    ///
+    /// Pool lock is acquired first, so local lock is never contended by threads
+    /// of this process. Poisoning is ignored: slot table lives in shm and is
+    /// checked when a process dies while updating it, not when a thread panics.
+    fn memory_pool(&self, _guard: &RobustLockGuard<'_>) -> MutexGuard<'_, MemoryPool<'a>> {
+        let mut memory_pool = self
+            .memory_pool
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if memory_pool.slot_count() != self.header.slot_count() {
+            *memory_pool = unsafe { map_memory_pool(self.shmem.as_ptr(), self.header) };
+        }
+        memory_pool
+    }
+
+    /// Take space from end of data region to add slots.
+    ///
+    /// Slot count is doubled if possible, otherwise a smaller amount of slots is added.
+    fn grow_slot_table(&self, memory_pool: &mut MemoryPool<'a>) -> Result<(), ShmError> {
+        let slot_count = memory_pool.slot_count();
+        let old_layout = self.header.layout();
+
+        let mut increment = slot_count.max(1);
+        let new_layout = loop {
+            if increment == 0 {
+                return Err(ArrayPoolError::NoFreeBlocLeft.into());
+            }
+
+            let new_count = slot_count + increment;
+            let bytes_count =
+                SegmentLayout::table_size(new_count) - SegmentLayout::table_size(slot_count);
+            if bytes_count <= old_layout.data_size && memory_pool.shrink_data(bytes_count).is_ok() {
+                break SegmentLayout::new(
+                    new_count,
+                    self.header.name_count(),
+                    self.header.segment_size(),
+                );
+            }
+            increment /= 2;
+        };
+
+        // Move name table, then slots (index is rebuilt from scratch)
+        let raw_ptr = self.shmem.as_ptr();
+        unsafe {
+            std::ptr::copy(
+                raw_ptr.add(old_layout.names_offset),
+                raw_ptr.add(new_layout.names_offset),
+                old_layout.name_count * NAME_ENTRY_SIZE,
+            );
+            std::ptr::copy(
+                raw_ptr.add(old_layout.slots_offset),
+                raw_ptr.add(new_layout.slots_offset),
+                slot_count * MEMORY_SLOT_SIZE,
+            );
+        }
+        let (slots, _index) = unsafe { new_layout.arrays(raw_ptr) };
+        slots[slot_count..].fill(MemorySlot::empty());
+
+        self.header.set_slot_count(new_layout.slot_count);
+        *memory_pool = unsafe { map_memory_pool(raw_ptr, self.header) };
+        memory_pool.rebuild_index();
+
+        Ok(())
+    }
+
+    /// Create a new segment and give its data region to memory pool.
+    ///
+    /// New segment is as large as first segment, or larger if required to store
+    /// `min_data_size` bytes.
+    fn add_segment(
+        &self,
+        memory_pool: &mut MemoryPool<'a>,
+        min_data_size: usize,
+    ) -> Result<(), ShmError> {
+        let segment = self.header.segment_count();
+        if segment >= self.header.max_segment_count().min(MAX_SEGMENT_COUNT) {
+            return Err(ArrayPoolError::NoSpaceLeft.into());
+        }
+
+        // Map previous segments, so new one is pushed at the right place
+        self.segment_ptr(segment - 1)?;
+
+        let size = self
+            .header
+            .segment_size()
+            .max(SegmentLayout::segment_size(0, 0, min_data_size));
+        let mut shmem = ShmemConf::new()
+            .size(size)
+            .flink(segment_file_path(&self.segment_path, segment))
+            .create()?;
+
+        let header = unsafe { &mut *(shmem.as_ptr() as *mut ShmHeader) };
+        *header = ShmHeader::new(0, size, self.header.alignment(), self.header.strategy());
+        header.set_ready();
+
+        // Segment file is removed on drop if no slot can be used to reference it
+        let data_size = header.layout().data_size;
+        if memory_pool.add_segment(segment as u16, data_size).is_err() {
+            self.grow_slot_table(memory_pool)?;
+            memory_pool.add_segment(segment as u16, data_size)?;
+        }
+
+        // Segment files are removed when first segment owner drops pool
+        shmem.set_owner(self.shmem.is_owner());
+        self.segments
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Segment(shmem));
+        self.header
+            .segment_count
+            .store(segment as u64 + 1, Ordering::Release);
+
+        Ok(())
+    }
+
+    /// Get pointer to start of a segment, mapping it (and previous ones) if required.
+    fn segment_ptr(&self, segment: usize) -> Result<*mut u8, ShmError> {
+        if segment == 0 {
+            return Ok(self.shmem.as_ptr());
+        }
+
+        let mut segments = self.segments.lock().unwrap_or_else(PoisonError::into_inner);
+        while segments.len() < segment {
+            let mut shmem = ShmemConf::new()
+                .flink(segment_file_path(&self.segment_path, segments.len() + 1))
+                .open()?;
+            read_header(&shmem)?.valid(shmem.len())?;
+
+            shmem.set_owner(self.shmem.is_owner());
+            segments.push(Segment(shmem));
+        }
+
+        Ok(segments[segment - 1].as_ptr())
+    }
+
+    /// Get pointer to data region of every segment.
+    fn data_ptrs(&self) -> Result<Vec<*mut u8>, ShmError> {
+        (0..self.header.segment_count())
+            .map(|segment| Ok(unsafe { self.segment_ptr(segment)?.add(self.offset_data) }))
+            .collect()
+    }
+
+    fn compact_locked(&self, memory_pool: &mut MemoryPool<'a>) -> Result<usize, ShmError> {
+        let data_ptrs = self.data_ptrs()?;
+        Ok(memory_pool.compact(|segment, from, to, size| unsafe {
+            let data_ptr = data_ptrs[segment];
+            std::ptr::copy(data_ptr.add(from), data_ptr.add(to), size);
+        }))
+    }
+
+    /// Drop references held by processes which are not running anymore.
+    ///
+    /// Objects unused once references are dropped are released. Returns number
+    /// of released objects.
+    pub fn reclaim_dead_processes(&self) -> Result<usize, ShmError> {
+        let guard = self.lock()?;
+        Ok(self
+            .memory_pool(&guard)
+            .reclaim_dead_processes(process_alive))
+    }
+
+    /// Get pool usage statistics.
+    pub fn stats(&self) -> Result<PoolStats, ShmError> {
+        let guard = self.lock()?;
+        Ok(self.memory_pool(&guard).stats())
+    }
+
+    /// Dump memory info to stdout.
+    pub fn dump(&self) -> Result<String, ShmError> {
+        let guard = self.lock()?;
+        Ok(self.memory_pool(&guard).dump())
+    }
+
+    fn data_from(&self, obj_mem_info: ObjectInfo) -> Result<ObjectData<'_>, ShmError> {
+        let segment_ptr = self.segment_ptr(obj_mem_info.segment())?;
+        let data_offset = obj_mem_info.offset() + self.offset_data;
+
+        Ok(ObjectData {
+            ptr: unsafe { segment_ptr.add(data_offset) },
+            len: obj_mem_info.size(),
+            _marker: PhantomData,
+        })
+    }
+}
+
+/// Read magic value of a segment (`None` if segment is too small to contain it).
+fn header_magic(shmem: &Shmem) -> Option<u64> {
+    (shmem.len() >= std::mem::size_of::<u64>())
+        .then(|| unsafe { &*(shmem.as_ptr() as *const AtomicU64) }.load(Ordering::Acquire))
+}
+
+/// Wait until segment has been initialized by its creator.
+///
+/// Segment is visible (zero filled) as soon as its file is created, before its
+/// header is written. Segments of other formats are not waited for. Fails with
+/// [`ShmError::CreatorDied`] if creator is not running anymore.
+fn wait_initialized(shmem: &Shmem, timeout: Duration) -> Result<(), ShmError> {
+    if !matches!(header_magic(shmem), Some(0 | SHM_HEADER_MAGIC)) {
+        return Ok(());
+    }
+
+    let header = read_header(shmem)?;
+    let deadline = Instant::now().checked_add(timeout);
+    let mut backoff = Backoff::new();
+    while header.state.load(Ordering::Acquire) == STATE_INITIALIZING {
+        if deadline.is_some_and(|x| Instant::now() >= x) {
+            return Err(ShmError::WaitTimeout);
+        }
+
+        // Creator liveness is only checked once waiting long enough
+        let creator_pid = header.creator_pid();
+        if creator_pid != 0
+            && backoff.is_sleeping()
+            && !process_alive(creator_pid)
+            && header.creator_pid() == creator_pid
+        {
+            return Err(ShmError::CreatorDied(creator_pid));
+        }
+
+        backoff.snooze(&header.state, STATE_INITIALIZING, deadline);
+    }
+    Ok(())
+}
+
+/// Get header of a mapped segment.
+///
+/// Fails if mapping is too small to contain a header.
+fn read_header<'a>(shmem: &Shmem) -> Result<&'a ShmHeader, ShmError> {
+    if shmem.len() < SHM_HEADER_SIZE {
+        return Err(ShmError::SegmentTooSmall {
+            expected: SHM_HEADER_SIZE,
+            actual: shmem.len(),
+        });
+    }
+    Ok(unsafe { &*(shmem.as_ptr() as *const ShmHeader) })
+}
+
+/// Map memory pool stored in segment.
+///
+/// # Safety
+///
+/// Pointer must point to a segment described by `header` and lock must be held.
+unsafe fn map_memory_pool<'a>(raw_ptr: *mut u8, header: &'a ShmHeader) -> MemoryPool<'a> {
+    let layout = header.layout();
+    let (slots, index) = layout.arrays(raw_ptr);
+
+    MemoryPool::new(slots, index)
+        .with_alignment(header.alignment())
+        .with_base_offset(layout.data_offset)
+        .with_strategy(header.strategy())
+        .with_generation_counter(&header.next_generation)
+        .with_release_event(&header.released)
+}
+
+impl<'a> fmt::Debug for ShmObjectPool<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShmObjectPool").finish()
+    }
+}
+
+/// Helper to create new shm object pool.
+#[derive(Debug)]
+pub struct ShmObjectPoolBuilder {
+    slot_count: usize,
+    data_size: usize,
+    alignment: usize,
+    strategy: AllocationStrategy,
+    auto_compact: bool,
+    max_segment_count: usize,
+    name_count: usize,
+    lock_timeout: Duration,
+    segment_path: PathBuf,
+}
+
+impl ShmObjectPoolBuilder {
+    /// Create default pool configuration.
+    pub fn new() -> Self {
+        Self {
+            slot_count: 10_000,
+            data_size: 512 * 1024 * 1024,
+            alignment: DEFAULT_ALIGNMENT,
+            strategy: AllocationStrategy::FirstFit,
+            auto_compact: false,
+            max_segment_count: 1,
+            name_count: 256,
+            lock_timeout: DEFAULT_LOCK_TIMEOUT,
+            segment_path: "/dev/shm/obj_pool.seg".into(),
+        }
+    }
+
+    /// Set pool slot count.
+    pub fn slot_count(mut self, value: usize) -> Self {
+        self.slot_count = value;
+        self
+    }
+
+    /// Set pool data size.
+    pub fn data_size(mut self, value: usize) -> Self {
+        self.data_size = value;
+        self
+    }
+
+    /// Set default alignment of objects data.
+    ///
+    /// Value must be a power of two.
+    pub fn alignment(mut self, value: usize) -> Self {
+        self.alignment = value;
+        self
+    }
+
+    /// Set policy used to find free blocks.
+    ///
+    /// Policy is stored in segment so all processes use the same one.
+    pub fn strategy(mut self, value: AllocationStrategy) -> Self {
+        self.strategy = value;
+        self
+    }
+
+    /// Compact pool when an allocation fails because of fragmentation.
+    pub fn auto_compact(mut self, value: bool) -> Self {
+        self.auto_compact = value;
+        self
+    }
+
+    /// Set maximum number of segments used by pool.
+    ///
+    /// When pool is full, a new segment (as large as the first one) is created
+    /// until this limit is reached. Additional segments are stored next to pool
+    /// file, with segment index as suffix.
+    pub fn max_segment_count(mut self, value: usize) -> Self {
+        self.max_segment_count = value;
+        self
+    }
+
+    /// Set maximum number of named objects.
+    pub fn name_count(mut self, value: usize) -> Self {
+        self.name_count = value;
+        self
+    }
+
+    /// Set maximum duration to wait for pool lock.
+    pub fn lock_timeout(mut self, value: Duration) -> Self {
+        self.lock_timeout = value;
+        self
+    }
+
+    /// Set pool file path.
+    pub fn segment_path<P>(mut self, value: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        self.segment_path = value.as_ref().into();
+        self
+    }
+
+    /// Create pool with current configuration.
+    pub fn create<'a>(&self) -> Result<ShmObjectPool<'a>, ShmError> {
+        check_alignment(self.alignment)?;
+
+        let size = SegmentLayout::segment_size(self.slot_count, self.name_count, self.data_size);
+
+        // Open segment
+        let shmem = ShmemConf::new()
+            .size(size)
+            .flink(&self.segment_path)
+            .create()?;
+
+        // Processes opening segment check creator is still running
+        read_header(&shmem)?
+            .creator_pid
+            .store(std::process::id(), Ordering::Release);
+
+        self.init_segment(shmem)
+    }
+
+    /// Initialize a segment created by current process with current configuration.
+    fn init_segment<'a>(&self, shmem: Shmem) -> Result<ShmObjectPool<'a>, ShmError> {
+        let size = shmem.len();
+        let expected = SegmentLayout::segment_size(self.slot_count, self.name_count, 0);
+        if size < expected {
+            return Err(ShmError::SegmentTooSmall {
+                expected,
+                actual: size,
+            });
+        }
+        let layout = SegmentLayout::new(self.slot_count, self.name_count, size);
+
+        let raw_ptr = shmem.as_ptr();
+
+        // Init header
+        let header = unsafe { &mut *(raw_ptr as *mut ShmHeader) };
+        *header = ShmHeader::new(self.slot_count, size, self.alignment, self.strategy);
+        header.max_segment_count = self.max_segment_count as u64;
+        header.name_count = self.name_count as u64;
+        if self.auto_compact {
+            header.flags |= FLAG_HEADER_AUTO_COMPACT;
+        }
+        header.set_slot_count(self.slot_count);
+        let header: &'a ShmHeader = header;
+
+        // Create object pool
+        let (slots, index) = unsafe { layout.arrays(raw_ptr) };
+        unsafe { layout.names(raw_ptr) }.fill(NameEntry::empty());
+
+        let memory_pool = MemoryPool::from_uninit_slice(slots, index, layout.data_size)
+            .with_alignment(self.alignment)
+            .with_base_offset(layout.data_offset)
+            .with_strategy(self.strategy)
+            .with_generation_counter(&header.next_generation)
+            .with_release_event(&header.released);
+
+        // Processes opening segment were waiting for it
+        header.set_ready();
+
+        Ok(ShmObjectPool {
+            shmem: Segment(shmem),
+            segment_path: self.segment_path.clone(),
+            segments: Mutex::new(Vec::new()),
+            header,
+            memory_pool: Mutex::new(memory_pool),
+            offset_data: layout.data_offset,
+            lock_timeout: self.lock_timeout,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Open pool if its segment exists, otherwise create it with current configuration.
+    ///
+    /// Segment file is created exclusively, so when several processes start at
+    /// once, only one of them creates pool while others wait for its
+    /// initialization to complete. Configuration is ignored when opening pool,
+    /// unless its creator died during initialization: segment is then
+    /// initialized again (keeping its size) by one of the waiting processes.
+    pub fn open_or_create<'a>(&self) -> Result<ShmObjectPool<'a>, ShmError> {
+        loop {
+            match self.create() {
+                Err(ShmError::SegmentAlreadyExists) => {}
+                result => return result,
+            }
+
+            // Segment may have been removed by its owner in between
+            match ShmObjectPool::open_with_timeout(&self.segment_path, self.lock_timeout) {
+                Err(ShmError::FileSystemError(_)) if !self.segment_path.exists() => {}
+                Err(ShmError::CreatorDied(creator_pid)) => {
+                    if let Some(pool) = self.take_over(creator_pid)? {
+                        return Ok(pool);
+                    }
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Initialize segment whose creator died during initialization.
+    ///
+    /// Returns `None` if another process took over segment first.
+    fn take_over<'a>(&self, creator_pid: u32) -> Result<Option<ShmObjectPool<'a>>, ShmError> {
+        let mut shmem = match ShmemConf::new().flink(&self.segment_path).open() {
+            Err(_) if !self.segment_path.exists() => return Ok(None),
+            result => result?,
+        };
+
+        let pid = std::process::id();
+        if read_header(&shmem)?
+            .creator_pid
+            .compare_exchange(creator_pid, pid, Ordering::AcqRel, Ordering::Relaxed)
+            .is_err()
+        {
+            return Ok(None);
+        }
+
+        // Segment is removed by current process instead of its dead creator
+        shmem.set_owner(true);
+        self.init_segment(shmem).map(Some)
+    }
+}
+
+impl Default for ShmObjectPoolBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod shm_header {
+        use super::*;
+
+        #[test]
+        fn test_valid() {
+            let mut header =
+                ShmHeader::new(10, 4096, DEFAULT_ALIGNMENT, AllocationStrategy::BestFit);
+            assert_eq!(header.valid(4096), Ok(()));
+            assert_eq!(header.strategy(), AllocationStrategy::BestFit);
+            assert_eq!(header.creator_pid(), std::process::id());
+            assert!(header.created_at() <= SystemTime::now());
+
+            assert_eq!(
+                header.valid(4000),
+                Err(ShmError::SegmentTooSmall {
+                    expected: 4096,
+                    actual: 4000
+                })
+            );
+
+            header.pointer_width = POINTER_WIDTH / 2;
+            assert_eq!(
+                header.valid(4096),
+                Err(ShmError::PointerWidthMismatch {
+                    expected: POINTER_WIDTH,
+                    actual: POINTER_WIDTH / 2
+                })
+            );
+            header.pointer_width = POINTER_WIDTH;
+            assert_eq!(header.valid(4096), Ok(()));
+
+            header.strategy = 42;
+            assert_eq!(
+                header.valid(4096),
+                Err(ShmError::InvalidAllocationStrategy(42))
+            );
+
+            header.version = SHM_VERSION + 1;
+            assert_eq!(
+                header.valid(4096),
+                Err(ShmError::UnsupportedShmVersion(SHM_VERSION + 1))
+            );
+
+            header.magic = 0x00;
+            assert_eq!(header.valid(4096), Err(ShmError::InvalidShmMagicValue));
+        }
+
+        #[test]
+        fn test_valid_layout() {
+            let mut header =
+                ShmHeader::new(10, 4096, DEFAULT_ALIGNMENT, AllocationStrategy::FirstFit);
+            let layout = header.layout();
+
+            // Layout is checked against its checksum
+            header.data_size.store(1, Ordering::Relaxed);
+            assert_eq!(header.valid(4096), Err(ShmError::InvalidLayoutChecksum));
+            header.set_slot_count(10);
+            assert_eq!(header.valid(4096), Ok(()));
+
+            // Stored offsets and sizes must match layout
+            header.data_offset = layout.data_offset as u64 + 8;
+            header
+                .layout_checksum
+                .store(header.compute_layout_checksum(), Ordering::Relaxed);
+            assert_eq!(
+                header.valid(4096),
+                Err(ShmError::InvalidDataOffset {
+                    expected: layout.data_offset as u64,
+                    actual: layout.data_offset as u64 + 8
+                })
+            );
+            header.data_offset = layout.data_offset as u64;
+
+            header
+                .data_size
+                .store(layout.data_size as u64 - 8, Ordering::Relaxed);
+            header
+                .layout_checksum
+                .store(header.compute_layout_checksum(), Ordering::Relaxed);
+            assert_eq!(
+                header.valid(4096),
+                Err(ShmError::InvalidDataSize {
+                    expected: layout.data_size as u64,
+                    actual: layout.data_size as u64 - 8
+                })
+            );
+            header.set_slot_count(10);
+
+            header
+                .slots_offset
+                .store(layout.slots_offset as u64 - 8, Ordering::Relaxed);
+            header
+                .layout_checksum
+                .store(header.compute_layout_checksum(), Ordering::Relaxed);
+            assert_eq!(
+                header.valid(4096),
+                Err(ShmError::InvalidSlotsOffset {
+                    expected: layout.slots_offset as u64,
+                    actual: layout.slots_offset as u64 - 8
+                })
+            );
+
+            // Tables must fit in segment
+            header.slot_count.store(u64::MAX / 2, Ordering::Relaxed);
+            header
+                .layout_checksum
+                .store(header.compute_layout_checksum(), Ordering::Relaxed);
+            assert_eq!(header.valid(4096), Err(ShmError::InvalidLayout));
+
+            header.set_slot_count(20);
+            assert_eq!(header.valid(4096), Ok(()));
+            assert_eq!(
+                header.data_size.load(Ordering::Relaxed),
+                header.layout().data_size as u64
+            );
+        }
+
+        #[test]
+        fn test_format() {
+            let header = ShmHeader::new(10, 4096, DEFAULT_ALIGNMENT, AllocationStrategy::BestFit);
+            let bytes = unsafe {
+                std::slice::from_raw_parts(
+                    &header as *const ShmHeader as *const u8,
+                    SHM_HEADER_SIZE,
+                )
+            };
+
+            // Fields can be read by tools, without knowing struct definition
+            let read_u64 =
+                |offset: usize| u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap());
+            assert_eq!(read_u64(0), SHM_HEADER_MAGIC);
+            assert_eq!(
+                u32::from_le_bytes(bytes[8..12].try_into().unwrap()),
+                SHM_VERSION
+            );
+            assert_eq!(bytes[13], AllocationStrategy::BestFit.as_raw());
+            assert_eq!(read_u64(32), 4096);
+            assert_eq!(read_u64(40), SHM_HEADER_SIZE as u64);
+            assert_eq!(read_u64(56), header.layout().slots_offset as u64);
+            assert_eq!(read_u64(64), 10);
+            assert_eq!(read_u64(104), DEFAULT_ALIGNMENT as u64);
+        }
+    }
+
+    mod shm_object_pool {
         use super::*;
+        use crate::descriptor::ByteOrder;
+        use std::{collections::VecDeque, sync::atomic::AtomicBool};
+
+        #[test]
+        fn test_create_and_open() -> anyhow::Result<()> {
+            let segment_path = "test_create_and_open.seg";
+            let python_id = PythonId(20);
+
+            let pool1 = ShmObjectPoolBuilder::new()
+                .segment_path(segment_path)
+                .create()?;
+            let pool2 = ShmObjectPool::open(segment_path)?;
+
+            // Check pool is empty
+            assert!(pool1.slice_of(python_id).is_none());
+            assert!(pool2.slice_of(python_id).is_none());
+
+            // Add object
+            pool1.add_object(python_id, 100)?;
+
+            // Check it is here in both pool
+            assert!(pool1.slice_of(python_id).is_some());
+            assert!(pool2.slice_of(python_id).is_some());
+
+            // Attach object from pool2, and detach from pool1
+            assert!(pool2.set_object_releasable(python_id).is_ok());
+            assert!(pool2.attach_object(python_id).is_ok());
+            assert_eq!(pool1.detach_object(python_id), Ok(()));
+
+            assert!(pool1.slice_of(python_id).is_some());
+            assert!(pool2.slice_of(python_id).is_some());
+
+            // Detach from pool2
+            assert_eq!(pool2.detach_object(python_id), Ok(()));
+
+            assert!(pool1.slice_of(python_id).is_none());
+            assert!(pool2.slice_of(python_id).is_none());
+
+            Ok(())
+        }
+
+        #[test]
+        fn test_open_invalid_header() -> anyhow::Result<()> {
+            let segment_path = "test_open_invalid_header.seg";
+
+            let pool = ShmObjectPoolBuilder::new()
+                .slot_count(16)
+                .data_size(4096)
+                .segment_path(segment_path)
+                .create()?;
+
+            let checksum = pool.header.layout_checksum.load(Ordering::Relaxed);
+            pool.header
+                .layout_checksum
+                .store(checksum + 1, Ordering::Relaxed);
+            assert_eq!(
+                ShmObjectPool::open(segment_path).err(),
+                Some(ShmError::InvalidLayoutChecksum)
+            );
+
+            // Layout is updated when slot table grows
+            pool.header
+                .layout_checksum
+                .store(checksum, Ordering::Relaxed);
+            for python_id in 1..=20 {
+                pool.add_object(PythonId(python_id), 10)?;
+            }
+            let pool2 = ShmObjectPool::open(segment_path)?;
+            assert_eq!(pool2.stats()?.object_count, 20);
+
+            Ok(())
+        }
+
+        #[test]
+        fn test_open_or_create() -> anyhow::Result<()> {
+            let segment_path = "test_open_or_create.seg";
+
+            let pool1 = ShmObjectPoolBuilder::new()
+                .slot_count(16)
+                .data_size(4096)
+                .segment_path(segment_path)
+                .open_or_create()?;
+            assert!(pool1.shmem.is_owner());
+            pool1.add_object(PythonId(20), 10)?;
+
+            // Existing pool is opened, whatever configuration is
+            let builder = ShmObjectPoolBuilder::new()
+                .slot_count(32)
+                .segment_path(segment_path);
+            assert_eq!(builder.create().err(), Some(ShmError::SegmentAlreadyExists));
+            let pool2 = builder.open_or_create()?;
+            assert!(!pool2.shmem.is_owner());
+            assert_eq!(pool2.header.slot_count(), 16);
+            assert!(pool2.slice_of(PythonId(20)).is_some());
+
+            Ok(())
+        }
+
+        #[test]
+        fn test_open_or_create_threads() -> anyhow::Result<()> {
+            let segment_path = "test_open_or_create_threads.seg";
+            let builder = ShmObjectPoolBuilder::new()
+                .slot_count(16)
+                .data_size(4096)
+                .segment_path(segment_path);
+
+            // Exactly one thread creates pool
+            let pools = std::thread::scope(|scope| {
+                let threads: Vec<_> = (1..=8)
+                    .map(|python_id| {
+                        let builder = &builder;
+                        scope.spawn(move || -> Result<ShmObjectPool, ShmError> {
+                            let pool = builder.open_or_create()?;
+                            pool.add_object(PythonId(python_id), 100)?;
+                            Ok(pool)
+                        })
+                    })
+                    .collect();
+                threads
+                    .into_iter()
+                    .map(|x| x.join().unwrap())
+                    .collect::<Result<Vec<_>, _>>()
+            })?;
+
+            assert_eq!(pools.iter().filter(|x| x.shmem.is_owner()).count(), 1);
+            for pool in &pools {
+                assert_eq!(pool.stats()?.object_count, 8);
+            }
+
+            Ok(())
+        }
+
+        #[test]
+        fn test_open_wait_initialized() -> anyhow::Result<()> {
+            let segment_path = "test_open_wait_initialized.seg";
+
+            let pool = ShmObjectPoolBuilder::new()
+                .slot_count(16)
+                .data_size(4096)
+                .segment_path(segment_path)
+                .create()?;
+
+            // Segment looks like being initialized by its creator
+            pool.header
+                .state
+                .store(STATE_INITIALIZING, Ordering::Release);
+            assert_eq!(
+                ShmObjectPool::open_with_timeout(
+                    Path::new(segment_path),
+                    Duration::from_millis(10)
+                )
+                .err(),
+                Some(ShmError::WaitTimeout)
+            );
+
+            std::thread::scope(|scope| {
+                scope.spawn(|| {
+                    std::thread::sleep(Duration::from_millis(20));
+                    pool.header.set_ready();
+                });
+                ShmObjectPool::open(segment_path).map(|_| ())
+            })?;
+
+            Ok(())
+        }
+
+        #[test]
+        fn test_shared_data() -> anyhow::Result<()> {
+            let segment_path = "test_shared_data.seg";
+            let python_id = PythonId(20);
+
+            let pool1 = ShmObjectPoolBuilder::new()
+                .segment_path(segment_path)
+                .create()?;
+            let pool2 = ShmObjectPool::open(segment_path)?;
+
+            // Attach objects
+            let slice1 = pool1.add_object(python_id, 100)?;
+            let slice2 = pool2.attach_object(python_id)?;
+
+            // Update data and check it is reflected correctly
+            unsafe { slice2.as_mut_slice()[0] = 0x12 }
+            assert_eq!(unsafe { slice1.as_slice() }[0], 0x12);
+            assert_eq!(unsafe { slice2.as_slice() }[0], 0x12);
+
+            unsafe { slice1.as_mut_slice()[0] = 0xAB }
+            assert_eq!(unsafe { slice1.as_slice() }[0], 0xAB);
+            assert_eq!(unsafe { slice2.as_slice() }[0], 0xAB);
+
+            unsafe { slice1.as_mut_slice()[10] = 0xFD }
+            assert_eq!(unsafe { slice1.as_slice() }[10], 0xFD);
+            assert_eq!(unsafe { slice2.as_slice() }[10], 0xFD);
+
+            Ok(())
+        }
+
+        #[test]
+        fn test_aligned_data() -> anyhow::Result<()> {
+            let segment_path = "test_aligned_data.seg";
+
+            let pool1 = ShmObjectPoolBuilder::new()
+                .slot_count(10)
+                .data_size(4096)
+                .alignment(128)
+                .segment_path(segment_path)
+                .create()?;
+            let pool2 = ShmObjectPool::open(segment_path)?;
+
+            let base_ptr1 = pool1.shmem.as_ptr() as usize;
+            let base_ptr2 = pool2.shmem.as_ptr() as usize;
+            let slice1 = pool1.add_object(PythonId(1), 3)?;
+            let slice2 = pool2.add_object(PythonId(2), 8)?;
+            let slice3 = pool1.add_object_aligned(PythonId(3), 8, 1024)?;
+            let (_, slice4) = pool2.allocate_aligned(8, 2048)?;
+
+            assert_eq!(slice1.len(), 3);
+            assert_eq!((slice1.as_ptr() as usize - base_ptr1) % 128, 0);
+            assert_eq!(slice2.len(), 8);
+            assert_eq!((slice2.as_ptr() as usize - base_ptr2) % 128, 0);
+            assert_eq!(slice3.len(), 8);
+            assert_eq!((slice3.as_ptr() as usize - base_ptr1) % 1024, 0);
+            assert_eq!(slice4.len(), 8);
+            assert_eq!((slice4.as_ptr() as usize - base_ptr2) % 2048, 0);
+
+            Ok(())
+        }
+
+        #[test]
+        fn test_shared_strategy() -> anyhow::Result<()> {
+            let segment_path = "test_shared_strategy.seg";
+
+            let pool1 = ShmObjectPoolBuilder::new()
+                .slot_count(10)
+                .data_size(4096)
+                .alignment(1)
+                .strategy(AllocationStrategy::BestFit)
+                .segment_path(segment_path)
+                .create()?;
+            let pool2 = ShmObjectPool::open(segment_path)?;
+
+            // Create holes of 100 and 20 bytes
+            pool1.add_object(PythonId(1), 100)?;
+            pool1.add_object(PythonId(2), 10)?;
+            pool1.add_object(PythonId(3), 20)?;
+            pool1.add_object(PythonId(4), 10)?;
+            for python_id in [PythonId(1), PythonId(3)] {
+                pool1.set_object_releasable(python_id)?;
+                pool1.detach_object(python_id)?;
+            }
+
+            // Check other process uses best fit too
+            let base_ptr = pool2.shmem.as_ptr() as usize;
+            let slice = pool2.add_object(PythonId(5), 15)?;
+            assert_eq!(slice.as_ptr() as usize - base_ptr, pool2.offset_data + 110);
+
+            Ok(())
+        }
+
+        #[test]
+        fn test_many_objects() -> anyhow::Result<()> {
+            let segment_path = "test_many_objects.seg";
+
+            let pool1 = ShmObjectPoolBuilder::new()
+                .slot_count(1000)
+                .data_size(1024 * 1024)
+                .segment_path(segment_path)
+                .create()?;
+            let pool2 = ShmObjectPool::open(segment_path)?;
+
+            for python_id in 1..1000 {
+                unsafe {
+                    pool1.add_object(PythonId(python_id), 8)?.as_mut_slice()[0] = python_id as u8
+                }
+            }
+            for python_id in (1..1000).step_by(2) {
+                pool2.set_object_releasable(PythonId(python_id))?;
+                pool1.detach_object(PythonId(python_id))?;
+            }
+            for python_id in 1..1000 {
+                let slice = pool2.slice_of(PythonId(python_id));
+                if python_id % 2 == 1 {
+                    assert!(slice.is_none());
+                } else {
+                    assert_eq!(
+                        slice.map(|x| unsafe { x.as_slice() }[0]),
+                        Some(python_id as u8)
+                    );
+                }
+            }
+
+            Ok(())
+        }
+
+        #[test]
+        fn test_grow_slot_table() -> anyhow::Result<()> {
+            let segment_path = "test_grow_slot_table.seg";
+
+            let pool1 = ShmObjectPoolBuilder::new()
+                .slot_count(4)
+                .data_size(64 * 1024)
+                .segment_path(segment_path)
+                .create()?;
+            let pool2 = ShmObjectPool::open(segment_path)?;
+
+            for python_id in 1..=20 {
+                unsafe {
+                    pool1.add_object(PythonId(python_id), 100)?.as_mut_slice()[0] = python_id as u8
+                }
+            }
+            assert_eq!(pool1.header.slot_count(), 32);
+
+            // Second process sees moved slot table
+            for python_id in 1..=20 {
+                let slice = pool2.attach_object(PythonId(python_id))?;
+                assert_eq!(unsafe { slice.as_slice() }[0], python_id as u8);
+            }
+            assert_eq!(pool2.add_object(PythonId(21), 100)?.len(), 100);
+            assert_eq!(pool1.slice_of(PythonId(21)).map(|x| x.len()), Some(100));
+
+            Ok(())
+        }
 
         #[test]
-        fn test_create_and_open() -> anyhow::Result<()> {
-            let segment_path = "test_create_and_open.seg";
-            let python_id = PythonId(20);
+        fn test_named_object() -> anyhow::Result<()> {
+            let segment_path = "test_named_object.seg";
 
             let pool1 = ShmObjectPoolBuilder::new()
+                .slot_count(4)
+                .name_count(2)
+                .data_size(64 * 1024)
                 .segment_path(segment_path)
                 .create()?;
             let pool2 = ShmObjectPool::open(segment_path)?;
 
-            // Check pool is empty
-            assert_eq!(pool1.slice_of(python_id), None);
-            assert_eq!(pool2.slice_of(python_id), None);
+            let (handle, slice) = pool1.add_named("weights", 100)?;
+            unsafe { slice.as_mut_slice().fill(7) }
+            assert_eq!(
+                pool2.add_named("weights", 10).map(|(handle, _)| handle),
+                Err(ShmError::PoolError(ArrayPoolError::NameAlreadyExists))
+            );
+            assert_eq!(
+                pool2.add_named("", 10).map(|(handle, _)| handle),
+                Err(ShmError::PoolError(ArrayPoolError::InvalidName))
+            );
+
+            // Name table is moved with slot table
+            for python_id in 1..=20 {
+                pool1.add_object(PythonId(python_id), 100)?;
+            }
+            assert_eq!(pool2.lookup("weights")?, Some(handle));
+            assert_eq!(pool2.names()?, ["weights"]);
+
+            let (attached_handle, slice) = pool2.attach_named("weights")?;
+            assert_eq!(attached_handle, handle);
+            assert!(unsafe { slice.as_slice() }.iter().all(|x| *x == 7));
+            assert_eq!(
+                pool2.attach_named("bias").map(|(handle, _)| handle),
+                Err(ShmError::PoolError(ArrayPoolError::NameNotFound))
+            );
+
+            pool1.add_named("bias", 10)?;
+            assert_eq!(
+                pool1.add_named("other", 10).map(|(handle, _)| handle),
+                Err(ShmError::PoolError(ArrayPoolError::NameTableFull))
+            );
+
+            // Name is removed once object is released
+            pool1.set_object_releasable(handle)?;
+            pool1.detach_object(handle)?;
+            pool2.detach_object(handle)?;
+            assert_eq!(pool1.lookup("weights")?, None);
+            assert_eq!(pool1.names()?, ["bias"]);
+            assert_eq!(pool2.add_named("weights", 10)?.1.len(), 10);
 
-            // Add object
-            pool1.add_object(python_id, 100)?;
+            Ok(())
+        }
 
-            // Check it is here in both pool
-            assert!(pool1.slice_of(python_id).is_some());
-            assert!(pool2.slice_of(python_id).is_some());
+        #[test]
+        fn test_reclaim_dead_processes() -> anyhow::Result<()> {
+            let segment_path = "test_reclaim_dead_processes.seg";
 
-            // Attach object from pool2, and detach from pool1
-            assert!(pool2.set_object_releasable(python_id).is_ok());
-            assert!(pool2.attach_object(python_id).is_ok());
-            assert_eq!(pool1.detach_object(python_id), Ok(()));
+            let pool = ShmObjectPoolBuilder::new()
+                .slot_count(16)
+                .data_size(4096)
+                .segment_path(segment_path)
+                .create()?;
 
-            assert!(pool1.slice_of(python_id).is_some());
-            assert!(pool2.slice_of(python_id).is_some());
+            let python_id = PythonId(42);
+            pool.add_object(python_id, 100)?;
+            pool.add_object(PythonId(43), 100)?;
+
+            // Attach object from a child process which exits without detaching it
+            let pid = unsafe { libc::fork() };
+            if pid == 0 {
+                let code = if pool.attach_object(python_id).is_ok() {
+                    0
+                } else {
+                    1
+                };
+                unsafe { libc::_exit(code) };
+            }
+            assert!(pid > 0);
+
+            let mut status = 0;
+            assert_eq!(unsafe { libc::waitpid(pid, &mut status, 0) }, pid);
+            assert!(libc::WIFEXITED(status) && libc::WEXITSTATUS(status) == 0);
+
+            pool.detach_object(python_id)?;
+            assert!(pool.slice_of(python_id).is_some());
+
+            assert_eq!(pool.reclaim_dead_processes()?, 1);
+            assert!(pool.slice_of(python_id).is_none());
+            assert!(pool.slice_of(PythonId(43)).is_some());
+            assert_eq!(pool.reclaim_dead_processes()?, 0);
 
-            // Detach from pool2
-            assert_eq!(pool2.detach_object(python_id), Ok(()));
+            Ok(())
+        }
+
+        /// Take pool lock from a child process which exits without releasing it.
+        fn lock_from_dead_process(pool: &ShmObjectPool, corrupt: bool) {
+            let pid = unsafe { libc::fork() };
+            if pid == 0 {
+                std::mem::forget(pool.header.lock());
+                if corrupt {
+                    // Move first slot (offset is stored after python ID)
+                    let slots_offset = pool.header.layout().slots_offset;
+                    unsafe { *(pool.shmem.as_ptr().add(slots_offset + 8) as *mut usize) = 42 };
+                }
+                unsafe { libc::_exit(0) };
+            }
+            assert!(pid > 0);
+            assert_eq!(unsafe { libc::waitpid(pid, std::ptr::null_mut(), 0) }, pid);
+        }
+
+        /// Create segment from a child process which exits before initializing it.
+        fn create_from_dead_process(segment_path: &str, size: usize) -> u32 {
+            let pid = unsafe { libc::fork() };
+            if pid == 0 {
+                let shmem = ShmemConf::new().size(size).flink(segment_path).create();
+                let code = match shmem.as_ref().map(read_header) {
+                    Ok(Ok(header)) => {
+                        header
+                            .creator_pid
+                            .store(std::process::id(), Ordering::Release);
+                        0
+                    }
+                    _ => 1,
+                };
+                unsafe { libc::_exit(code) };
+            }
+            assert!(pid > 0);
+
+            let mut status = 0;
+            assert_eq!(unsafe { libc::waitpid(pid, &mut status, 0) }, pid);
+            assert!(libc::WIFEXITED(status) && libc::WEXITSTATUS(status) == 0);
+            pid as u32
+        }
+
+        #[test]
+        fn test_creator_died() -> anyhow::Result<()> {
+            let segment_path = "test_creator_died.seg";
+            let builder = ShmObjectPoolBuilder::new()
+                .slot_count(16)
+                .data_size(4096)
+                .segment_path(segment_path);
+
+            let size = SegmentLayout::segment_size(
+                builder.slot_count,
+                builder.name_count,
+                2 * builder.data_size,
+            );
+            let creator_pid = create_from_dead_process(segment_path, size);
+            assert_eq!(
+                ShmObjectPool::open(segment_path).err(),
+                Some(ShmError::CreatorDied(creator_pid))
+            );
+
+            // Segment is initialized again, keeping its size
+            let pool1 = builder.open_or_create()?;
+            assert!(pool1.shmem.is_owner());
+            assert_eq!(pool1.header.creator_pid(), std::process::id());
+            assert_eq!(pool1.header.slot_count(), 16);
+            assert_eq!(pool1.header.valid(size), Ok(()));
+            pool1.add_object(PythonId(42), 5000)?;
+
+            let pool2 = builder.open_or_create()?;
+            assert!(!pool2.shmem.is_owner());
+            assert!(pool2.slice_of(PythonId(42)).is_some());
+
+            Ok(())
+        }
+
+        #[test]
+        fn test_lock_owner_died() -> anyhow::Result<()> {
+            let segment_path = "test_lock_owner_died.seg";
+
+            let pool = ShmObjectPoolBuilder::new()
+                .slot_count(16)
+                .data_size(4096)
+                .segment_path(segment_path)
+                .create()?;
+            pool.add_object(PythonId(42), 100)?;
+
+            // Lock is taken over and slot table is checked
+            lock_from_dead_process(&pool, false);
+            assert_eq!(pool.add_object(PythonId(43), 100)?.len(), 100);
 
-            assert_eq!(pool1.slice_of(python_id), None);
-            assert_eq!(pool2.slice_of(python_id), None);
+            // Error is reported until slot table is fixed
+            lock_from_dead_process(&pool, true);
+            for _ in 0..2 {
+                assert_eq!(
+                    pool.stats().map(|x| x.object_count),
+                    Err(ShmError::PoolError(ArrayPoolError::CorruptedSlotTable))
+                );
+            }
 
             Ok(())
         }
 
         #[test]
-        fn test_shared_data() -> anyhow::Result<()> {
-            let segment_path = "test_shared_data.seg";
-            let python_id = PythonId(20);
+        fn test_lock_timeout() -> anyhow::Result<()> {
+            let segment_path = "test_lock_timeout.seg";
+
+            let pool1 = ShmObjectPoolBuilder::new()
+                .slot_count(16)
+                .data_size(4096)
+                .lock_timeout(Duration::from_millis(20))
+                .segment_path(segment_path)
+                .create()?;
+            let pool2 = ShmObjectPool::open(segment_path)?.with_lock_timeout(Duration::ZERO);
+
+            {
+                let _guard = pool1.header.lock();
+                assert_eq!(
+                    pool1.add_object(PythonId(42), 10).map(|x| x.len()),
+                    Err(ShmError::LockTimeout)
+                );
+                assert_eq!(
+                    pool2.stats().map(|x| x.object_count),
+                    Err(ShmError::LockTimeout)
+                );
+            }
+
+            assert_eq!(pool2.add_object(PythonId(42), 10)?.len(), 10);
+
+            Ok(())
+        }
+
+        #[test]
+        fn test_attach_object_wait() -> anyhow::Result<()> {
+            let segment_path = "test_attach_object_wait.seg";
+
+            let pool = ShmObjectPoolBuilder::new()
+                .slot_count(16)
+                .data_size(4096)
+                .segment_path(segment_path)
+                .create()?;
+
+            let python_id = PythonId(42);
+            assert_eq!(
+                pool.attach_object_wait(python_id, Duration::from_millis(10))
+                    .map(|x| x.len()),
+                Err(ShmError::WaitTimeout)
+            );
+
+            let producer = std::thread::spawn(move || -> Result<(), ShmError> {
+                let pool = ShmObjectPool::open(segment_path)?;
+                std::thread::sleep(Duration::from_millis(20));
+                pool.add_object(PythonId(41), 10)?;
+                std::thread::sleep(Duration::from_millis(20));
+                pool.add_object(python_id, 20)?;
+                Ok(())
+            });
+
+            let slice = pool.attach_object_wait(python_id, Duration::from_secs(10))?;
+            assert_eq!(slice.len(), 20);
+            producer.join().unwrap()?;
+
+            Ok(())
+        }
+
+        #[test]
+        fn test_add_object_blocking() -> anyhow::Result<()> {
+            let segment_path = "test_add_object_blocking.seg";
+
+            let pool = ShmObjectPoolBuilder::new()
+                .slot_count(16)
+                .data_size(1024)
+                .alignment(1)
+                .segment_path(segment_path)
+                .create()?;
+
+            let python_id = PythonId(1);
+            pool.add_object(python_id, 1000)?;
+            assert_eq!(
+                pool.add_object_blocking(PythonId(2), 500, Duration::from_millis(10))
+                    .map(|x| x.len()),
+                Err(ShmError::WaitTimeout)
+            );
+
+            let consumer = std::thread::spawn(move || -> Result<(), ShmError> {
+                let pool = ShmObjectPool::open(segment_path)?;
+                std::thread::sleep(Duration::from_millis(20));
+                pool.set_object_releasable(python_id)?;
+                pool.detach_object(python_id)?;
+                Ok(())
+            });
+
+            let slice = pool.add_object_blocking(PythonId(2), 500, Duration::from_secs(10))?;
+            assert_eq!(slice.len(), 500);
+            consumer.join().unwrap()?;
+
+            // Other errors are reported immediately
+            assert_eq!(
+                pool.add_object_blocking(PythonId(2), 10, Duration::from_secs(10))
+                    .map(|x| x.len()),
+                Err(ShmError::PoolError(ArrayPoolError::ObjectAlreadyExists(
+                    PythonId(2)
+                )))
+            );
+
+            Ok(())
+        }
+
+        #[test]
+        fn test_send_sync() {
+            fn assert_send_sync<T: Send + Sync>() {}
+            assert_send_sync::<ShmObjectPool>();
+        }
+
+        /// Check slot table of pool is consistent and contains no object.
+        fn assert_empty_pool(pool: &ShmObjectPool) -> anyhow::Result<()> {
+            let guard = pool.lock()?;
+            let mut memory_pool = pool.memory_pool(&guard);
+            memory_pool.check_consistency()?;
+            assert_eq!(memory_pool.stats().object_count, 0);
+            Ok(())
+        }
+
+        #[test]
+        fn test_threads_stress() -> anyhow::Result<()> {
+            let segment_path = "test_threads_stress.seg";
+
+            // Small slot table, so it is grown while other threads use pool
+            let pool = ShmObjectPoolBuilder::new()
+                .slot_count(4)
+                .data_size(1024 * 1024)
+                .alignment(8)
+                .auto_compact(true)
+                .segment_path(segment_path)
+                .create()?;
+
+            std::thread::scope(|scope| {
+                let threads: Vec<_> = (0..8u64)
+                    .map(|thread_idx| {
+                        let pool = &pool;
+                        scope.spawn(move || -> Result<(), ShmError> {
+                            for round in 0..4u64 {
+                                let mut handles = Vec::new();
+                                for i in 0..50u64 {
+                                    let python_id =
+                                        PythonId(thread_idx * 1000 + round * 100 + i + 1);
+                                    let size = 16 + (i as usize % 7) * 64;
+                                    unsafe {
+                                        pool.add_object(python_id, size)?
+                                            .as_mut_slice()
+                                            .fill(thread_idx as u8)
+                                    }
+                                    handles.push(python_id.into());
+
+                                    let (handle, slice) = pool.allocate(size)?;
+                                    unsafe { slice.as_mut_slice().fill(thread_idx as u8) }
+                                    handles.push(handle);
+                                }
+
+                                for handle in handles {
+                                    let slice = pool.attach_object(handle)?;
+                                    assert!(unsafe { slice.as_slice() }
+                                        .iter()
+                                        .all(|x| *x == thread_idx as u8));
+                                    pool.set_object_releasable(handle)?;
+                                    pool.detach_object(handle)?;
+                                    pool.detach_object(handle)?;
+                                }
+                            }
+                            Ok(())
+                        })
+                    })
+                    .collect();
+                threads.into_iter().try_for_each(|x| x.join().unwrap())
+            })?;
+
+            assert!(pool.header.slot_count() > 4);
+            assert_empty_pool(&pool)
+        }
+
+        #[test]
+        fn test_threads_object_data() -> anyhow::Result<()> {
+            let segment_path = "test_threads_object_data.seg";
+
+            let pool = ShmObjectPoolBuilder::new()
+                .slot_count(256)
+                .data_size(64 * 1024)
+                .alignment(8)
+                .segment_path(segment_path)
+                .create()?;
+            let done = AtomicBool::new(false);
+
+            // Unused objects are moved by compaction while threads access data of their objects
+            std::thread::scope(|scope| {
+                let compactor = scope.spawn(|| -> Result<(), ShmError> {
+                    while !done.load(Ordering::Acquire) {
+                        pool.compact()?;
+                    }
+                    Ok(())
+                });
+
+                let threads: Vec<_> = (0..4u64)
+                    .map(|thread_idx| {
+                        let pool = &pool;
+                        scope.spawn(move || -> Result<(), ShmError> {
+                            let check_and_release = |(python_id, value): (PythonId, u8)| {
+                                let data = pool.attach_object(python_id)?;
+                                assert!(unsafe { data.as_slice() }.iter().all(|x| *x == value));
+                                pool.set_object_releasable(python_id)?;
+                                pool.detach_object(python_id)
+                            };
+
+                            let mut unused = VecDeque::new();
+                            for round in 0..200u64 {
+                                let python_id = PythonId(thread_idx * 1000 + round + 1);
+                                let size = 64 + (round as usize % 5) * 40;
+
+                                // Object is only accessed by current thread
+                                let data = pool.add_object(python_id, size)?;
+                                unsafe { data.as_mut_slice().fill(round as u8) }
+                                pool.detach_object(python_id)?;
+
+                                unused.push_back((python_id, round as u8));
+                                if unused.len() > 4 {
+                                    check_and_release(unused.pop_front().unwrap())?;
+                                }
+                            }
+                            unused.into_iter().try_for_each(check_and_release)
+                        })
+                    })
+                    .collect();
+
+                let result = threads.into_iter().try_for_each(|x| x.join().unwrap());
+                done.store(true, Ordering::Release);
+                result.and(compactor.join().unwrap())
+            })?;
+
+            assert_empty_pool(&pool)
+        }
+
+        #[test]
+        fn test_threads_shared_objects() -> anyhow::Result<()> {
+            let segment_path = "test_threads_shared_objects.seg";
+
+            let pool = ShmObjectPoolBuilder::new()
+                .slot_count(64)
+                .data_size(4096)
+                .alignment(1)
+                .max_segment_count(8)
+                .name_count(0)
+                .segment_path(segment_path)
+                .create()?;
+
+            // Objects are spread over several segments, mapped by other threads on first use
+            let python_ids: Vec<_> = (1..=4).map(PythonId).collect();
+            std::thread::scope(|scope| {
+                let threads: Vec<_> = python_ids
+                    .iter()
+                    .map(|python_id| {
+                        let pool = &pool;
+                        scope.spawn(move || -> Result<(), ShmError> {
+                            unsafe {
+                                pool.add_object(*python_id, 3000)?
+                                    .as_mut_slice()
+                                    .fill(python_id.0 as u8)
+                            }
+                            pool.set_object_releasable(*python_id)?;
+                            Ok(())
+                        })
+                    })
+                    .collect();
+                threads.into_iter().try_for_each(|x| x.join().unwrap())
+            })?;
+            assert!(pool.header.segment_count() > 1);
+
+            std::thread::scope(|scope| {
+                let threads: Vec<_> = (0..8)
+                    .map(|_| {
+                        let pool = &pool;
+                        let python_ids = &python_ids;
+                        scope.spawn(move || -> Result<(), ShmError> {
+                            for _ in 0..100 {
+                                for (python_id, slice) in
+                                    python_ids.iter().zip(pool.attach_objects(python_ids)?)
+                                {
+                                    assert!(unsafe { slice.as_slice() }
+                                        .iter()
+                                        .all(|x| *x == python_id.0 as u8));
+                                }
+                                pool.detach_objects(python_ids)?;
+                            }
+                            Ok(())
+                        })
+                    })
+                    .collect();
+                threads.into_iter().try_for_each(|x| x.join().unwrap())
+            })?;
+
+            // Only references taken when adding objects are left
+            pool.detach_objects(&python_ids)?;
+            assert_empty_pool(&pool)
+        }
+
+        #[test]
+        fn test_grow_slot_table_full() -> anyhow::Result<()> {
+            let segment_path = "test_grow_slot_table_full.seg";
+
+            let pool = ShmObjectPoolBuilder::new()
+                .slot_count(2)
+                .data_size(1024)
+                .alignment(1)
+                .segment_path(segment_path)
+                .create()?;
+
+            // Table can only take space from trailing free block
+            assert_eq!(pool.add_object(PythonId(1), 1000)?.len(), 1000);
+            assert_eq!(
+                pool.add_object(PythonId(2), 10).err(),
+                Some(ShmError::PoolError(ArrayPoolError::NoFreeBlocLeft))
+            );
+
+            Ok(())
+        }
+
+        #[test]
+        fn test_allocate() -> anyhow::Result<()> {
+            let segment_path = "test_allocate.seg";
 
             let pool1 = ShmObjectPoolBuilder::new()
+                .slot_count(16)
+                .data_size(4096)
                 .segment_path(segment_path)
                 .create()?;
             let pool2 = ShmObjectPool::open(segment_path)?;
 
-            // Attach objects
-            let slice1 = pool1.add_object(python_id, 100)?;
-            let slice2 = pool2.attach_object(python_id)?;
+            let (handle1, slice) = pool1.allocate(10)?;
+            unsafe { slice.as_mut_slice().fill(1) }
+            assert_eq!(handle1.python_id(), PythonId(FIRST_POOL_PYTHON_ID));
 
-            // Update data and check it is reflected correctly
-            slice2[0] = 0x12;
-            assert_eq!(slice1[0], 0x12);
-            assert_eq!(slice2[0], 0x12);
+            // Caller supplied IDs are skipped
+            pool1.add_object(PythonId(FIRST_POOL_PYTHON_ID + 1), 10)?;
+
+            let descriptor = ArrayDescriptor::new("u1", ByteOrder::NotApplicable, &[20], &[1])?;
+            let (handle2, slice) = pool2.allocate_described(20, &descriptor)?;
+            unsafe { slice.as_mut_slice().fill(2) }
+            assert_eq!(handle2.python_id(), PythonId(FIRST_POOL_PYTHON_ID + 2));
+            assert_eq!(pool1.describe(handle2.python_id()), Ok(Some(descriptor)));
+
+            // IDs are not reused once object is released
+            pool1.set_object_releasable(handle1)?;
+            pool1.detach_object(handle1)?;
+            let (handle3, _) = pool1.allocate(10)?;
+            assert_eq!(handle3.python_id(), PythonId(FIRST_POOL_PYTHON_ID + 3));
+
+            assert!(unsafe { pool2.attach_object(handle2)?.as_slice() }
+                .iter()
+                .all(|x| *x == 2));
+
+            Ok(())
+        }
+
+        #[test]
+        fn test_stale_handle() -> anyhow::Result<()> {
+            let segment_path = "test_stale_handle.seg";
+
+            let pool1 = ShmObjectPoolBuilder::new()
+                .slot_count(16)
+                .data_size(4096)
+                .segment_path(segment_path)
+                .create()?;
+            let pool2 = ShmObjectPool::open(segment_path)?;
+
+            let python_id = PythonId(42);
+            pool1.add_object(python_id, 10)?;
+            let handle = pool2.handle_of(python_id)?;
+            assert_eq!(pool1.handle_of(python_id), Ok(handle));
+
+            // Release object and add a new one with same ID
+            pool1.set_object_releasable(handle)?;
+            pool1.detach_object(handle)?;
+            pool1.add_object(python_id, 20)?;
+
+            let new_handle = pool1.handle_of(python_id)?;
+            assert_ne!(new_handle, handle);
+            assert_eq!(
+                pool2.attach_object(handle).err(),
+                Some(ShmError::PoolError(ArrayPoolError::StaleHandle(python_id)))
+            );
+            assert_eq!(
+                pool2.detach_objects(&[new_handle, handle]),
+                Err(ShmError::PoolError(ArrayPoolError::StaleHandle(python_id)))
+            );
+            assert_eq!(pool2.attach_object(new_handle)?.len(), 20);
+
+            // Plain python ID is not checked
+            pool2.detach_object(python_id)?;
+
+            Ok(())
+        }
+
+        #[test]
+        fn test_described_object() -> anyhow::Result<()> {
+            let segment_path = "test_described_object.seg";
 
-            slice1[0] = 0xAB;
-            assert_eq!(slice1[0], 0xAB);
-            assert_eq!(slice2[0], 0xAB);
+            let pool1 = ShmObjectPoolBuilder::new()
+                .slot_count(16)
+                .data_size(4096)
+                .alignment(1)
+                .auto_compact(true)
+                .segment_path(segment_path)
+                .create()?;
+            let pool2 = ShmObjectPool::open(segment_path)?;
+
+            let descriptor = ArrayDescriptor::new("<u2", ByteOrder::Little, &[3, 5], &[10, 2])?;
+            pool1.add_object(PythonId(1), 10)?;
+            unsafe {
+                pool1
+                    .add_described_object(PythonId(2), 30, &descriptor)?
+                    .as_mut_slice()
+                    .fill(2)
+            }
+
+            assert_eq!(pool2.describe(PythonId(1)), Ok(None));
+            assert_eq!(pool2.describe(PythonId(2)), Ok(Some(descriptor)));
+            assert_eq!(
+                pool2.describe(PythonId(3)),
+                Err(ShmError::PoolError(ArrayPoolError::ObjectNotFound(
+                    PythonId(3)
+                )))
+            );
+
+            // Descriptor and data are kept when object is moved
+            pool1.set_object_releasable(PythonId(1))?;
+            pool1.detach_object(PythonId(1))?;
+            pool1.detach_object(PythonId(2))?;
+            assert_eq!(pool2.compact()?, 10);
+
+            assert_eq!(pool2.describe(PythonId(2)), Ok(Some(descriptor)));
+            let slice = pool2.attach_object(PythonId(2))?;
+            assert_eq!(slice.len(), 30);
+            assert!(unsafe { slice.as_slice() }.iter().all(|x| *x == 2));
+
+            let slice = pool2.resize_object(PythonId(2), 3000)?;
+            assert!(unsafe { slice.as_slice() }[..30].iter().all(|x| *x == 2));
+            assert_eq!(pool1.describe(PythonId(2)), Ok(Some(descriptor)));
+
+            Ok(())
+        }
+
+        #[test]
+        fn test_batch() -> anyhow::Result<()> {
+            let segment_path = "test_batch.seg";
+
+            let pool1 = ShmObjectPoolBuilder::new()
+                .slot_count(16)
+                .data_size(1024)
+                .alignment(1)
+                .segment_path(segment_path)
+                .create()?;
+            let pool2 = ShmObjectPool::open(segment_path)?;
+
+            let slices = pool1.add_objects(&[(PythonId(1), 10), (PythonId(2), 20)])?;
+            assert_eq!(slices.iter().map(|x| x.len()).collect::<Vec<_>>(), [10, 20]);
+            for (python_id, slice) in (1..).zip(slices) {
+                unsafe { slice.as_mut_slice().fill(python_id) }
+            }
+
+            let slices = pool2.attach_objects(&[PythonId(2), PythonId(1)])?;
+            assert_eq!(unsafe { slices[0].as_slice() }[0], 2);
+            assert_eq!(unsafe { slices[1].as_slice() }[0], 1);
+
+            pool1.set_object_releasable(PythonId(1))?;
+            pool1.set_object_releasable(PythonId(2))?;
+            pool1.detach_objects(&[PythonId(1), PythonId(2)])?;
+            assert_eq!(pool1.stats()?.object_count, 2);
+            pool2.detach_objects(&[PythonId(1), PythonId(2)])?;
+            assert_eq!(pool1.stats()?.object_count, 0);
+
+            Ok(())
+        }
+
+        #[test]
+        fn test_batch_all_or_nothing() -> anyhow::Result<()> {
+            let segment_path = "test_batch_all_or_nothing.seg";
+
+            let pool = ShmObjectPoolBuilder::new()
+                .slot_count(16)
+                .data_size(1024)
+                .alignment(1)
+                .segment_path(segment_path)
+                .create()?;
+
+            assert_eq!(
+                pool.add_objects(&[(PythonId(1), 500), (PythonId(2), 500), (PythonId(3), 500)])
+                    .err(),
+                Some(ShmError::PoolError(ArrayPoolError::NoSpaceLeft))
+            );
+            assert_eq!(pool.stats()?.object_count, 0);
+            assert_eq!(pool.stats()?.largest_free_block, 1024);
+
+            pool.add_object(PythonId(1), 10)?;
+            assert_eq!(
+                pool.attach_objects(&[PythonId(1), PythonId(2)]).err(),
+                Some(ShmError::PoolError(ArrayPoolError::ObjectNotFound(
+                    PythonId(2)
+                )))
+            );
+            assert_eq!(
+                pool.detach_objects(&[PythonId(1), PythonId(2)]),
+                Err(ShmError::PoolError(ArrayPoolError::ObjectNotFound(
+                    PythonId(2)
+                )))
+            );
+
+            assert_eq!(
+                pool.detach_objects(&[PythonId(1), PythonId(1)]),
+                Err(ShmError::PoolError(ArrayPoolError::DuplicateHandle(
+                    PythonId(1)
+                )))
+            );
+
+            let handle = pool.handle_of(PythonId(1))?;
+            let stale = ObjectHandle::new(PythonId(1), handle.generation() + 1);
+            assert_eq!(
+                pool.attach_objects(&[handle, stale]).err(),
+                Some(ShmError::PoolError(ArrayPoolError::StaleHandle(PythonId(
+                    1
+                ))))
+            );
+
+            // Refcount is unchanged: object is released on first detach
+            pool.set_object_releasable(PythonId(1))?;
+            pool.detach_objects(&[PythonId(1)])?;
+            assert_eq!(pool.stats()?.object_count, 0);
+
+            Ok(())
+        }
+
+        #[test]
+        fn test_batch_rollback() -> anyhow::Result<()> {
+            let segment_path = "test_batch_rollback.seg";
+
+            let pool = ShmObjectPoolBuilder::new()
+                .slot_count(16)
+                .data_size(1024)
+                .alignment(1)
+                .segment_path(segment_path)
+                .create()?;
+            unsafe { pool.add_object(PythonId(9), 10)?.as_mut_slice().fill(9) }
+            let stats = pool.stats()?;
+
+            // Batch fails on third entry, once two objects have been added
+            assert_eq!(
+                pool.add_objects(&[
+                    (PythonId(1), 100),
+                    (PythonId(2), 100),
+                    (PythonId(9), 10),
+                    (PythonId(3), 100)
+                ])
+                .err(),
+                Some(ShmError::PoolError(ArrayPoolError::ObjectAlreadyExists(
+                    PythonId(9)
+                )))
+            );
+            assert_eq!(pool.stats()?, stats);
+            assert!(pool.slice_of(PythonId(1)).is_none());
+            assert!(pool.slice_of(PythonId(2)).is_none());
+            assert!(pool
+                .slice_of(PythonId(9))
+                .is_some_and(|x| unsafe { x.as_slice() }.iter().all(|x| *x == 9)));
+
+            Ok(())
+        }
+
+        #[test]
+        fn test_resize_object() -> anyhow::Result<()> {
+            let segment_path = "test_resize_object.seg";
+
+            let pool1 = ShmObjectPoolBuilder::new()
+                .slot_count(16)
+                .data_size(4096)
+                .segment_path(segment_path)
+                .create()?;
+            let pool2 = ShmObjectPool::open(segment_path)?;
+
+            unsafe { pool1.add_object(PythonId(1), 100)?.as_mut_slice().fill(1) }
+            unsafe { pool1.add_object(PythonId(2), 100)?.as_mut_slice().fill(2) }
+
+            // Shrink in place
+            let slice = pool2.resize_object(PythonId(1), 50)?;
+            assert_eq!(slice.len(), 50);
+            assert!(unsafe { slice.as_slice() }.iter().all(|x| *x == 1));
+
+            // Move and keep data
+            let slice = pool2.resize_object(PythonId(1), 1000)?;
+            assert_eq!(slice.len(), 1000);
+            assert!(unsafe { slice.as_slice() }[..50].iter().all(|x| *x == 1));
+            let slice = pool1.attach_object(PythonId(1))?;
+            assert_eq!(slice.len(), 1000);
+            assert!(unsafe { slice.as_slice() }[..50].iter().all(|x| *x == 1));
+
+            assert_eq!(
+                pool1.resize_object(PythonId(2), 4096).err(),
+                Some(ShmError::PoolError(ArrayPoolError::NoSpaceLeft))
+            );
+            assert!(unsafe { pool1.slice_of(PythonId(2)).unwrap().as_slice() }
+                .iter()
+                .all(|x| *x == 2));
+
+            Ok(())
+        }
+
+        #[test]
+        fn test_resize_described_object() -> anyhow::Result<()> {
+            let segment_path = "test_resize_described_object.seg";
+
+            let pool = ShmObjectPoolBuilder::new()
+                .slot_count(16)
+                .data_size(1024)
+                .alignment(1)
+                .max_segment_count(2)
+                .segment_path(segment_path)
+                .create()?;
+
+            // New segment must be able to store descriptor too
+            let descriptor = ArrayDescriptor::new("u1", ByteOrder::NotApplicable, &[100], &[1])?;
+            unsafe {
+                pool.add_described_object(PythonId(1), 100, &descriptor)?
+                    .as_mut_slice()
+                    .fill(1)
+            }
+            let slice = pool.resize_object(PythonId(1), 65536)?;
+            assert_eq!(slice.len(), 65536);
+            assert!(unsafe { slice.as_slice() }[..100].iter().all(|x| *x == 1));
+            assert_eq!(pool.describe(PythonId(1)), Ok(Some(descriptor)));
+
+            Ok(())
+        }
+
+        #[test]
+        fn test_stats() -> anyhow::Result<()> {
+            let segment_path = "test_stats.seg";
+
+            let pool1 = ShmObjectPoolBuilder::new()
+                .slot_count(16)
+                .data_size(1024)
+                .alignment(1)
+                .max_segment_count(2)
+                .segment_path(segment_path)
+                .create()?;
+            let pool2 = ShmObjectPool::open(segment_path)?;
 
-            slice1[10] = 0xFD;
-            assert_eq!(slice1[10], 0xFD);
-            assert_eq!(slice2[10], 0xFD);
+            pool1.add_object(PythonId(1), 1000)?;
+            pool1.add_object(PythonId(2), 1000)?;
+
+            let stats = pool2.stats()?;
+            assert_eq!(stats.object_count, 2);
+            assert_eq!(stats.used_bytes, 2000);
+            assert_eq!(stats.free_block_count, 2);
+            assert_eq!(stats.slots_used, 4);
+            assert_eq!(stats.slot_count, 16);
+            assert_eq!(stats.total_bytes, stats.used_bytes + stats.free_bytes);
+
+            Ok(())
+        }
+
+        #[test]
+        fn test_multi_segment() -> anyhow::Result<()> {
+            let segment_path = "test_multi_segment.seg";
+
+            let pool1 = ShmObjectPoolBuilder::new()
+                .slot_count(16)
+                .data_size(1024)
+                .alignment(1)
+                .max_segment_count(3)
+                .name_count(0)
+                .segment_path(segment_path)
+                .create()?;
+            let pool2 = ShmObjectPool::open(segment_path)?;
+
+            // Each object requires a new segment
+            for python_id in 1..=2 {
+                unsafe {
+                    pool1
+                        .add_object(PythonId(python_id), 1000)?
+                        .as_mut_slice()
+                        .fill(python_id as u8)
+                }
+            }
+            unsafe { pool2.add_object(PythonId(3), 4000)?.as_mut_slice().fill(3) }
+            assert_eq!(pool1.header.segment_count(), 3);
+            assert!(Path::new("test_multi_segment.seg.2").exists());
+
+            // Memory pool uses data region recorded in segment headers
+            let data_size = (0..3)
+                .map(|segment| {
+                    let ptr = pool2.segment_ptr(segment)?;
+                    Ok(unsafe { &*(ptr as *const ShmHeader) }.layout().data_size)
+                })
+                .sum::<Result<usize, ShmError>>()?;
+            assert_eq!(pool2.stats()?.total_bytes, data_size);
+
+            assert_eq!(
+                pool1.add_object(PythonId(4), 3000).err(),
+                Some(ShmError::PoolError(ArrayPoolError::NoSpaceLeft))
+            );
+
+            // Segments are mapped on first use by other processes
+            for python_id in 1..=3 {
+                let slice = pool2.attach_object(PythonId(python_id))?;
+                assert!(unsafe { slice.as_slice() }
+                    .iter()
+                    .all(|x| *x == python_id as u8));
+                let slice = pool1.attach_object(PythonId(python_id))?;
+                assert!(unsafe { slice.as_slice() }
+                    .iter()
+                    .all(|x| *x == python_id as u8));
+            }
+            assert_eq!(pool1.slice_of(PythonId(3)).map(|x| x.len()), Some(4000));
+
+            // Space released in additional segment is reused
+            pool1.set_object_releasable(PythonId(2))?;
+            for _ in 0..3 {
+                pool1.detach_object(PythonId(2))?;
+            }
+            assert_eq!(pool2.add_object(PythonId(4), 2000)?.len(), 2000);
+
+            // Owner removes every segment
+            drop(pool2);
+            drop(pool1);
+            assert!(!Path::new("test_multi_segment.seg.1").exists());
+            assert!(!Path::new("test_multi_segment.seg.2").exists());
+
+            Ok(())
+        }
+
+        #[test]
+        fn test_owner_removes_unmapped_segments() -> anyhow::Result<()> {
+            let segment_path = "test_owner_removes_unmapped_segments.seg";
+
+            let pool1 = ShmObjectPoolBuilder::new()
+                .slot_count(16)
+                .data_size(1024)
+                .alignment(1)
+                .max_segment_count(3)
+                .name_count(0)
+                .segment_path(segment_path)
+                .create()?;
+            let pool2 = ShmObjectPool::open(segment_path)?;
+
+            // Segments are only added (and mapped) by non owner
+            pool2.add_object(PythonId(1), 1000)?;
+            for python_id in 2..=3 {
+                pool2.add_object(PythonId(python_id), 4000)?;
+            }
+            assert_eq!(pool1.header.segment_count(), 3);
+            drop(pool2);
+            assert!(Path::new("test_owner_removes_unmapped_segments.seg.1").exists());
+            assert!(Path::new("test_owner_removes_unmapped_segments.seg.2").exists());
+
+            drop(pool1);
+            assert!(!Path::new("test_owner_removes_unmapped_segments.seg.1").exists());
+            assert!(!Path::new("test_owner_removes_unmapped_segments.seg.2").exists());
+
+            Ok(())
+        }
+
+        /// Fill pool with 10 objects of 100 bytes and release half of them.
+        fn fragment_pool(pool: &ShmObjectPool) -> anyhow::Result<()> {
+            for python_id in 1..=10 {
+                unsafe {
+                    pool.add_object(PythonId(python_id), 100)?
+                        .as_mut_slice()
+                        .fill(python_id as u8)
+                }
+            }
+            for python_id in 1..=10 {
+                if python_id % 2 == 1 {
+                    pool.set_object_releasable(PythonId(python_id))?;
+                }
+                pool.detach_object(PythonId(python_id))?;
+            }
+            Ok(())
+        }
+
+        #[test]
+        fn test_compact() -> anyhow::Result<()> {
+            let segment_path = "test_compact.seg";
+
+            let pool1 = ShmObjectPoolBuilder::new()
+                .slot_count(20)
+                .data_size(1000)
+                .alignment(1)
+                .segment_path(segment_path)
+                .create()?;
+            let pool2 = ShmObjectPool::open(segment_path)?;
+
+            fragment_pool(&pool1)?;
+            assert_eq!(
+                pool2.add_object(PythonId(20), 200).err(),
+                Some(ShmError::PoolError(ArrayPoolError::NoSpaceLeft))
+            );
+
+            assert_eq!(pool2.compact()?, 400);
+            assert_eq!(pool2.add_object(PythonId(20), 500)?.len(), 500);
+
+            // Check data has been moved
+            for python_id in (2..=10).step_by(2) {
+                let slice = pool1.attach_object(PythonId(python_id))?;
+                assert!(unsafe { slice.as_slice() }
+                    .iter()
+                    .all(|x| *x == python_id as u8));
+            }
+
+            Ok(())
+        }
+
+        #[test]
+        fn test_compact_skips_pinned() -> anyhow::Result<()> {
+            let segment_path = "test_compact_skips_pinned.seg";
+
+            let pool = ShmObjectPoolBuilder::new()
+                .slot_count(20)
+                .data_size(1000)
+                .alignment(1)
+                .segment_path(segment_path)
+                .create()?;
+
+            fragment_pool(&pool)?;
+            let slice = pool.slice_of(PythonId(2)).unwrap();
+
+            assert_eq!(pool.compact()?, 300);
+            assert!(unsafe { slice.as_slice() }.iter().all(|x| *x == 2));
+            assert_eq!(pool.slice_of(PythonId(2)).unwrap().as_ptr(), slice.as_ptr());
+
+            Ok(())
+        }
+
+        #[test]
+        fn test_auto_compact() -> anyhow::Result<()> {
+            let segment_path = "test_auto_compact.seg";
+
+            let pool1 = ShmObjectPoolBuilder::new()
+                .slot_count(20)
+                .data_size(1000)
+                .alignment(1)
+                .auto_compact(true)
+                .segment_path(segment_path)
+                .create()?;
+            let pool2 = ShmObjectPool::open(segment_path)?;
+
+            fragment_pool(&pool1)?;
+            assert_eq!(pool2.add_object(PythonId(20), 500)?.len(), 500);
+            assert_eq!(
+                pool2.add_object(PythonId(21), 1).err(),
+                Some(ShmError::PoolError(ArrayPoolError::NoSpaceLeft))
+            );
 
             Ok(())
         }
+
+        #[test]
+        fn test_invalid_alignment() {
+            let result = ShmObjectPoolBuilder::new()
+                .alignment(48)
+                .segment_path("test_invalid_alignment.seg")
+                .create();
+
+            assert_eq!(
+                result.err(),
+                Some(ShmError::PoolError(ArrayPoolError::InvalidAlignment(48)))
+            );
+        }
     }
 }
@@ -0,0 +1,685 @@
+/*! Migration of segments written with a previous format. */
+
+use std::{
+    ffi::OsString,
+    fs::File,
+    io::{Read, Write},
+    os::unix::io::AsRawFd,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, AtomicU8, Ordering},
+    time::{Duration, Instant},
+};
+
+use shared_memory::Shmem;
+
+use super::{
+    header_magic, SegmentLayout, ShmError, ShmHeader, SHM_HEADER_SIZE, SHM_SIZE_ALIGNMENT,
+    STATE_READY,
+};
+use crate::memory_info::{AllocationStrategy, MemoryPool, PythonId, DEFAULT_ALIGNMENT};
+
+/// Magic value of previous format (with native width fields).
+pub(super) const LEGACY_HEADER_MAGIC: u64 = 0xFF45_9831_ABAB_0001;
+
+/// Only version of previous format which can be migrated.
+const LEGACY_VERSION: u8 = 1;
+
+/// Version written while segment is migrated, so an interrupted migration is detected.
+const MIGRATING_VERSION: u8 = 0;
+
+/// Suffix appended to segment path to get path of migration journal.
+const JOURNAL_SUFFIX: &str = ".migration";
+
+/// Delay between attempts to lock segment file.
+const FILE_LOCK_RETRY_DELAY: Duration = Duration::from_millis(1);
+
+const LEGACY_HEADER_SIZE: usize = std::mem::size_of::<LegacyHeader>();
+const LEGACY_SLOT_SIZE: usize = std::mem::size_of::<LegacySlot>();
+
+/// Header of previous format, as written by a 64-bit process.
+///
+/// Memory slots are stored right after header, followed by data region.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+struct LegacyHeader {
+    magic: u64,
+    version: u8,
+    slot_count: u64,
+    /// Spin lock (non zero when locked).
+    spin_lock: u8,
+}
+
+/// Memory slot of previous format, as written by a 64-bit process.
+///
+/// Slots are sorted by offset: object offset is the sum of sizes of previous slots.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+struct LegacySlot {
+    python_id: u64,
+    size: u64,
+    refcount: u64,
+    source_pid: u32,
+    flags: u8,
+}
+
+const _: () = {
+    use std::mem::offset_of;
+    assert!(offset_of!(LegacyHeader, slot_count) == 16);
+    assert!(offset_of!(LegacyHeader, spin_lock) == 24);
+    assert!(LEGACY_HEADER_SIZE == 32);
+    assert!(offset_of!(LegacySlot, source_pid) == 24);
+    assert!(LEGACY_SLOT_SIZE == 32);
+};
+
+impl LegacyHeader {
+    /// Read header stored at the start of `bytes`.
+    fn read(bytes: &[u8]) -> Result<Self, ShmError> {
+        if bytes.len() < LEGACY_HEADER_SIZE {
+            return Err(ShmError::SegmentTooSmall {
+                expected: LEGACY_HEADER_SIZE,
+                actual: bytes.len(),
+            });
+        }
+        Ok(unsafe { std::ptr::read_unaligned(bytes.as_ptr() as *const Self) })
+    }
+
+    /// Check header of a segment of `segment_len` bytes can be migrated.
+    fn check(&self, segment_len: usize) -> Result<(), ShmError> {
+        if self.magic != LEGACY_HEADER_MAGIC {
+            return Err(ShmError::InvalidShmMagicValue);
+        }
+        if self.version == MIGRATING_VERSION {
+            return Err(ShmError::MigrationFailed(
+                "previous migration has been interrupted".into(),
+            ));
+        }
+        if self.version != LEGACY_VERSION {
+            return Err(ShmError::UnsupportedShmVersion(self.version as u32));
+        }
+        if self.slot_count == 0
+            || self.slot_count
+                > (segment_len.saturating_sub(LEGACY_HEADER_SIZE) / LEGACY_SLOT_SIZE) as u64
+        {
+            return Err(ShmError::InvalidLayout);
+        }
+        Ok(())
+    }
+
+    /// Get offset of data region.
+    const fn data_offset(&self) -> usize {
+        LEGACY_HEADER_SIZE + self.slot_count as usize * LEGACY_SLOT_SIZE
+    }
+
+    /// Read memory slots stored in `bytes` (starting with header) of a checked
+    /// segment of `segment_len` bytes.
+    ///
+    /// Returns objects with their offset in data region.
+    fn objects(
+        &self,
+        bytes: &[u8],
+        segment_len: usize,
+    ) -> Result<Vec<(LegacySlot, usize)>, ShmError> {
+        let bytes = &bytes[LEGACY_HEADER_SIZE..self.data_offset()];
+        let data_size = (segment_len - self.data_offset()) as u64;
+        let mut objects = Vec::new();
+        let mut offset = 0_u64;
+        for slot_bytes in bytes.chunks_exact(LEGACY_SLOT_SIZE) {
+            let slot =
+                unsafe { std::ptr::read_unaligned(slot_bytes.as_ptr() as *const LegacySlot) };
+            if slot.size > data_size - offset {
+                return Err(ShmError::InvalidLayout);
+            }
+            if slot.python_id != 0 {
+                objects.push((slot, offset as usize));
+            }
+            offset += slot.size;
+        }
+        Ok(objects)
+    }
+}
+
+/// Spin lock of previous format, released when dropped unless kept.
+struct LegacyLock<'a>(&'a AtomicU8);
+
+impl<'a> LegacyLock<'a> {
+    /// Take lock of a segment written with previous format.
+    ///
+    /// Previous format does not record which processes use segment, so lock is
+    /// not waited for.
+    fn try_acquire(shmem: &'a Shmem) -> Result<Self, ShmError> {
+        let spin_lock = unsafe { legacy_spin_lock(shmem.as_ptr()) };
+        spin_lock
+            .compare_exchange(0, 1, Ordering::Acquire, Ordering::Relaxed)
+            .map_err(|_| ShmError::MigrationFailed("segment is locked".into()))?;
+        Ok(Self(spin_lock))
+    }
+
+    /// Keep lock taken once segment starts being modified.
+    fn keep(self) {
+        std::mem::forget(self);
+    }
+}
+
+impl Drop for LegacyLock<'_> {
+    fn drop(&mut self) {
+        self.0.store(0, Ordering::Release);
+    }
+}
+
+/// Get spin lock of a segment written with previous format.
+///
+/// # Safety
+///
+/// Pointer must point to a segment large enough to contain legacy header.
+unsafe fn legacy_spin_lock<'a>(raw_ptr: *mut u8) -> &'a AtomicU8 {
+    &*(raw_ptr.add(std::mem::offset_of!(LegacyHeader, spin_lock)) as *const AtomicU8)
+}
+
+/// Exclusive lock on segment file, released when dropped or when process dies.
+struct FileLock(File);
+
+impl FileLock {
+    fn acquire(path: &Path, timeout: Duration) -> Result<Self, ShmError> {
+        let file = File::open(path).map_err(fs_error)?;
+        let deadline = Instant::now().checked_add(timeout);
+
+        while unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+            let err = std::io::Error::last_os_error();
+            if err.kind() != std::io::ErrorKind::WouldBlock {
+                return Err(fs_error(err));
+            }
+            if deadline.is_some_and(|x| Instant::now() >= x) {
+                return Err(ShmError::LockTimeout);
+            }
+            std::thread::sleep(FILE_LOCK_RETRY_DELAY);
+        }
+        Ok(Self(file))
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        unsafe { libc::flock(self.0.as_raw_fd(), libc::LOCK_UN) };
+    }
+}
+
+fn fs_error(err: std::io::Error) -> ShmError {
+    ShmError::FileSystemError(err.to_string())
+}
+
+/// Get path of migration journal of a segment.
+fn journal_path(segment_path: &Path) -> PathBuf {
+    let mut path = OsString::from(segment_path);
+    path.push(JOURNAL_SUFFIX);
+    path.into()
+}
+
+/// Write migrated header.
+///
+/// Magic value is written last, so openers never read a partially written header.
+/// Low byte of `created_at` overlaps spin lock of previous format: it is kept
+/// non zero, so processes using previous format never modify migrated segment.
+///
+/// # Safety
+///
+/// Pointer must point to a segment large enough to contain header.
+unsafe fn write_header(raw_ptr: *mut u8, header: ShmHeader) {
+    let magic = header.magic;
+    std::ptr::write(
+        raw_ptr as *mut ShmHeader,
+        ShmHeader {
+            magic: LEGACY_HEADER_MAGIC,
+            created_at: header.created_at | 1,
+            ..header
+        },
+    );
+    (*(raw_ptr as *const AtomicU64)).store(magic, Ordering::Release);
+}
+
+/// Get largest slot count (up to `slot_count`) of a segment of `segment_size`
+/// bytes storing `object_count` objects of `data_size` bytes.
+///
+/// One more slot is kept for free space.
+fn migrated_slot_count(
+    slot_count: usize,
+    object_count: usize,
+    data_size: usize,
+    segment_size: usize,
+) -> Option<usize> {
+    (object_count + 1..=slot_count.max(object_count + 1))
+        .rev()
+        .find(|x| SegmentLayout::segment_size(*x, 0, data_size) <= segment_size)
+}
+
+/// Migrate segment written with previous format.
+///
+/// Segment is migrated in place by first process opening it, while others wait
+/// on segment file lock. Objects are packed at the start of data region, since
+/// tables are now stored at the end of segment. Name table is empty.
+///
+/// Segment header, slots and objects data are first copied to a journal stored
+/// next to segment file, then segment is rewritten from journal. A migration
+/// interrupted while segment is rewritten is redone from its journal by next
+/// process opening segment.
+pub(super) fn migrate(
+    shmem: &Shmem,
+    segment_path: &Path,
+    timeout: Duration,
+) -> Result<(), ShmError> {
+    let _lock = FileLock::acquire(segment_path, timeout)?;
+
+    // Segment may have been migrated while waiting for lock
+    if header_magic(shmem) != Some(LEGACY_HEADER_MAGIC) {
+        return Ok(());
+    }
+
+    let journal_path = journal_path(segment_path);
+    let mut journal = match File::open(&journal_path) {
+        Ok(journal) => journal,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            write_journal(shmem, &journal_path)?
+        }
+        Err(err) => return Err(fs_error(err)),
+    };
+    replay_journal(shmem, &mut journal)?;
+    std::fs::remove_file(&journal_path).map_err(fs_error)
+}
+
+/// Copy header, slots and objects data of a segment to its journal.
+///
+/// Segment is not modified if journal cannot be written. Otherwise, spin lock of
+/// previous format is kept and segment version is set to [`MIGRATING_VERSION`].
+fn write_journal(shmem: &Shmem, journal_path: &Path) -> Result<File, ShmError> {
+    let bytes = unsafe { std::slice::from_raw_parts(shmem.as_ptr(), shmem.len()) };
+    let legacy = LegacyHeader::read(bytes)?;
+    legacy.check(shmem.len())?;
+
+    // Objects must not be modified by processes using previous format
+    let lock = LegacyLock::try_acquire(shmem)?;
+    let objects = legacy.objects(bytes, shmem.len())?;
+    let in_use = objects.iter().filter(|(slot, _)| slot.refcount > 0).count();
+    if in_use > 0 {
+        return Err(ShmError::ObjectsInUse(in_use));
+    }
+    migrated_layout(&legacy, &objects, shmem.len())?;
+
+    // Journal is renamed once complete, so an incomplete journal is never replayed
+    let mut tmp_path = OsString::from(journal_path);
+    tmp_path.push(".tmp");
+    let write = || -> std::io::Result<()> {
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(&bytes[..legacy.data_offset()])?;
+        for (slot, offset) in &objects {
+            let from = legacy.data_offset() + offset;
+            file.write_all(&bytes[from..from + slot.size as usize])?;
+        }
+        std::fs::rename(&tmp_path, journal_path)
+    };
+    if let Err(err) = write() {
+        let _ = std::fs::remove_file(&tmp_path);
+        return Err(fs_error(err));
+    }
+
+    lock.keep();
+    unsafe {
+        *shmem
+            .as_ptr()
+            .add(std::mem::offset_of!(LegacyHeader, version)) = MIGRATING_VERSION
+    };
+    File::open(journal_path).map_err(fs_error)
+}
+
+/// Get slot count and data size of migrated segment.
+fn migrated_layout(
+    legacy: &LegacyHeader,
+    objects: &[(LegacySlot, usize)],
+    segment_len: usize,
+) -> Result<(usize, usize), ShmError> {
+    let segment_size = segment_len / SHM_SIZE_ALIGNMENT * SHM_SIZE_ALIGNMENT;
+    let data_size = objects.iter().map(|(slot, _)| slot.size as usize).sum();
+    let slot_count = migrated_slot_count(
+        legacy.slot_count as usize,
+        objects.len(),
+        data_size,
+        segment_size,
+    )
+    .ok_or_else(|| ShmError::MigrationFailed("segment is too small to store objects".into()))?;
+    Ok((slot_count, segment_size))
+}
+
+/// Rewrite segment in current format from its journal.
+///
+/// Segment content is only read from journal, so journal can be replayed again
+/// if replay is interrupted.
+fn replay_journal(shmem: &Shmem, journal: &mut File) -> Result<(), ShmError> {
+    let mut bytes = vec![0; LEGACY_HEADER_SIZE];
+    journal.read_exact(&mut bytes).map_err(fs_error)?;
+    let legacy = LegacyHeader::read(&bytes)?;
+    legacy.check(shmem.len())?;
+    bytes.resize(legacy.data_offset(), 0);
+    journal
+        .read_exact(&mut bytes[LEGACY_HEADER_SIZE..])
+        .map_err(fs_error)?;
+    let objects = legacy.objects(&bytes, shmem.len())?;
+    let (slot_count, segment_size) = migrated_layout(&legacy, &objects, shmem.len())?;
+
+    let raw_ptr = shmem.as_ptr();
+    unsafe {
+        legacy_spin_lock(raw_ptr).store(1, Ordering::Relaxed);
+        *raw_ptr.add(std::mem::offset_of!(LegacyHeader, version)) = MIGRATING_VERSION;
+    }
+
+    // Objects are packed at the start of data region
+    let mut to = SHM_HEADER_SIZE;
+    for (slot, _) in &objects {
+        let data = unsafe { std::slice::from_raw_parts_mut(raw_ptr.add(to), slot.size as usize) };
+        journal.read_exact(data).map_err(fs_error)?;
+        to += slot.size as usize;
+    }
+
+    // Tables are written once data has been copied
+    let header = ShmHeader::new(
+        slot_count,
+        segment_size,
+        DEFAULT_ALIGNMENT,
+        AllocationStrategy::default(),
+    );
+    let layout = header.layout();
+    let (slots, index) = unsafe { layout.arrays(raw_ptr) };
+    let mut memory_pool = MemoryPool::from_uninit_slice(slots, index, layout.data_size)
+        .with_base_offset(layout.data_offset)
+        .with_generation_counter(&header.next_generation);
+
+    let mut to = SHM_HEADER_SIZE;
+    for (slot, _) in &objects {
+        let offset = memory_pool.add_migrated_object(
+            PythonId(slot.python_id),
+            slot.size as usize,
+            slot.refcount as usize,
+            slot.source_pid,
+            slot.flags,
+        )?;
+        debug_assert_eq!(layout.data_offset + offset, to);
+        to += slot.size as usize;
+    }
+
+    header.state.store(STATE_READY, Ordering::Relaxed);
+    unsafe { write_header(raw_ptr, header) };
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use shared_memory::ShmemConf;
+
+    use super::*;
+    use crate::shm::ShmObjectPool;
+
+    /// Create segment with previous format, as a 64-bit process would.
+    ///
+    /// Objects data is filled with their python ID, slots with a python ID of 0
+    /// are free blocks.
+    fn create_legacy(
+        segment_path: &str,
+        slots: &[LegacySlot],
+        slot_count: usize,
+        data_size: usize,
+    ) -> anyhow::Result<Shmem> {
+        let header = LegacyHeader {
+            magic: LEGACY_HEADER_MAGIC,
+            version: LEGACY_VERSION,
+            slot_count: slot_count as u64,
+            spin_lock: 0,
+        };
+        let shmem = ShmemConf::new()
+            .size(header.data_offset() + data_size)
+            .flink(segment_path)
+            .create()?;
+
+        let raw_ptr = shmem.as_ptr();
+        let mut offset = header.data_offset();
+        unsafe {
+            std::ptr::write(raw_ptr as *mut LegacyHeader, header);
+            for (idx, slot) in slots.iter().enumerate() {
+                let slot_ptr = raw_ptr.add(LEGACY_HEADER_SIZE + idx * LEGACY_SLOT_SIZE);
+                std::ptr::write(slot_ptr as *mut LegacySlot, *slot);
+                std::ptr::write_bytes(
+                    raw_ptr.add(offset),
+                    slot.python_id as u8,
+                    slot.size as usize,
+                );
+                offset += slot.size as usize;
+            }
+        }
+        Ok(shmem)
+    }
+
+    const fn legacy_slot(python_id: u64, size: u64, refcount: u64, flags: u8) -> LegacySlot {
+        LegacySlot {
+            python_id,
+            size,
+            refcount,
+            source_pid: 1,
+            flags,
+        }
+    }
+
+    #[test]
+    fn test_migrate() -> anyhow::Result<()> {
+        let segment_path = "test_migrate.seg";
+        let slots = [
+            legacy_slot(20, 100, 0, 0x01),
+            legacy_slot(0, 30, 0, 0),
+            legacy_slot(21, 50, 0, 0),
+            legacy_slot(22, 1000, 0, 0x01),
+            legacy_slot(0, 7012, 0, 0),
+        ];
+        let _legacy = create_legacy(segment_path, &slots, 20, 8192)?;
+
+        let pool1 = ShmObjectPool::open(segment_path)?;
+        assert_eq!(pool1.header.check_format(), Ok(()));
+        assert_eq!(pool1.header.creator_pid(), std::process::id());
+        assert_eq!(pool1.header.alignment(), DEFAULT_ALIGNMENT);
+        assert_eq!(pool1.header.slot_count(), 20);
+        assert!(!journal_path(Path::new(segment_path)).exists());
+
+        // Objects and their data are kept
+        for (python_id, size) in [(20, 100), (21, 50), (22, 1000)] {
+            let slice = pool1.slice_of(PythonId(python_id)).unwrap();
+            assert_eq!(slice.len(), size);
+            assert!(unsafe { slice.as_slice() }
+                .iter()
+                .all(|x| *x == python_id as u8));
+        }
+        let stats = pool1.stats()?;
+        assert_eq!(stats.object_count, 3);
+        assert_eq!(stats.used_bytes, 1150);
+
+        // Processes using previous format cannot lock migrated segment anymore
+        assert_ne!(
+            unsafe { legacy_spin_lock(pool1.shmem.0.as_ptr()) }.load(Ordering::Relaxed),
+            0
+        );
+
+        // Pool can still be used, migrated segment is opened as is
+        assert_eq!(pool1.add_object(PythonId(23), 200)?.len(), 200);
+        let pool2 = ShmObjectPool::open(segment_path)?;
+        assert_eq!(pool2.slice_of(PythonId(23)).map(|x| x.len()), Some(200));
+        assert_eq!(
+            pool2
+                .slice_of(PythonId(21))
+                .map(|x| unsafe { x.as_slice() }[0]),
+            Some(21)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_shrinks_slot_table() -> anyhow::Result<()> {
+        let segment_path = "test_migrate_shrinks_slot_table.seg";
+        let slots = [legacy_slot(20, 1000, 0, 0), legacy_slot(0, 3096, 0, 0)];
+        let _legacy = create_legacy(segment_path, &slots, 40, 4096)?;
+
+        // Tables of current format take more space
+        let pool = ShmObjectPool::open(segment_path)?;
+        assert!(pool.header.slot_count() < 40);
+        assert!(unsafe { pool.slice_of(PythonId(20)).unwrap().as_slice() }
+            .iter()
+            .all(|x| *x == 20));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_moves_data_forward() -> anyhow::Result<()> {
+        let segment_path = "test_migrate_moves_data_forward.seg";
+        let slots = [
+            legacy_slot(20, 100, 0, 0),
+            legacy_slot(21, 100, 0, 0),
+            legacy_slot(0, 3896, 0, 0),
+        ];
+        let _legacy = create_legacy(segment_path, &slots, 3, 4096)?;
+
+        // Header of current format is larger than previous header and slot table
+        let pool = ShmObjectPool::open(segment_path)?;
+        for python_id in [20, 21] {
+            let slice = pool.slice_of(PythonId(python_id)).unwrap();
+            assert_eq!(slice.len(), 100);
+            assert!(unsafe { slice.as_slice() }
+                .iter()
+                .all(|x| *x == python_id as u8));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_unsupported() -> anyhow::Result<()> {
+        let segment_path = "test_migrate_unsupported.seg";
+        let slots = [legacy_slot(20, 100, 0, 0), legacy_slot(0, 3996, 0, 0)];
+        let legacy = create_legacy(segment_path, &slots, 20, 4096)?;
+        let header = unsafe { &mut *(legacy.as_ptr() as *mut LegacyHeader) };
+
+        header.version = 2;
+        assert_eq!(
+            ShmObjectPool::open(segment_path).err(),
+            Some(ShmError::UnsupportedShmVersion(2))
+        );
+
+        // Segment cannot be migrated while a process using previous format holds lock
+        header.version = LEGACY_VERSION;
+        header.spin_lock = 1;
+        assert_eq!(
+            ShmObjectPool::open(segment_path).err(),
+            Some(ShmError::MigrationFailed("segment is locked".into()))
+        );
+
+        // Slot table must fit in segment
+        header.spin_lock = 0;
+        header.slot_count = 0;
+        assert_eq!(
+            ShmObjectPool::open(segment_path).err(),
+            Some(ShmError::InvalidLayout)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_full_segment() -> anyhow::Result<()> {
+        let segment_path = "test_migrate_full_segment.seg";
+        let slots = [legacy_slot(20, 4096, 0, 0)];
+        let _legacy = create_legacy(segment_path, &slots, 4, 4096)?;
+
+        // Objects must still fit once tables are converted
+        assert_eq!(
+            ShmObjectPool::open(segment_path).err(),
+            Some(ShmError::MigrationFailed(
+                "segment is too small to store objects".into()
+            ))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_in_use() -> anyhow::Result<()> {
+        let segment_path = "test_migrate_in_use.seg";
+        let slots = [
+            legacy_slot(20, 100, 1, 0),
+            legacy_slot(21, 100, 0, 0),
+            legacy_slot(22, 100, 2, 0),
+            legacy_slot(0, 3796, 0, 0),
+        ];
+        let legacy = create_legacy(segment_path, &slots, 20, 4096)?;
+        let header = unsafe { &mut *(legacy.as_ptr() as *mut LegacyHeader) };
+
+        // Referenced objects may still be used by processes using previous format
+        assert_eq!(
+            ShmObjectPool::open(segment_path).err(),
+            Some(ShmError::ObjectsInUse(2))
+        );
+
+        // Segment is left untouched and unlocked
+        assert_eq!(header.version, LEGACY_VERSION);
+        assert_eq!(header.spin_lock, 0);
+        assert!(!journal_path(Path::new(segment_path)).exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_interrupted() -> anyhow::Result<()> {
+        let segment_path = "test_migrate_interrupted.seg";
+        let slots = [
+            legacy_slot(20, 100, 0, 0),
+            legacy_slot(0, 30, 0, 0),
+            legacy_slot(21, 500, 0, 0),
+            legacy_slot(0, 3466, 0, 0),
+        ];
+        let legacy = create_legacy(segment_path, &slots, 20, 4096)?;
+        let journal_path = journal_path(Path::new(segment_path));
+
+        // Interrupt migration while data is copied
+        write_journal(&legacy, &journal_path)?;
+        let header = unsafe { &*(legacy.as_ptr() as *const LegacyHeader) };
+        assert_eq!(header.version, MIGRATING_VERSION);
+        assert_ne!(header.spin_lock, 0);
+        unsafe { std::ptr::write_bytes(legacy.as_ptr().add(SHM_HEADER_SIZE), 0xFF, 800) };
+
+        // Migration is redone from journal by next process opening segment
+        let pool = ShmObjectPool::open(segment_path)?;
+        for (python_id, size) in [(20, 100), (21, 500)] {
+            let slice = pool.slice_of(PythonId(python_id)).unwrap();
+            assert_eq!(slice.len(), size);
+            assert!(unsafe { slice.as_slice() }
+                .iter()
+                .all(|x| *x == python_id as u8));
+        }
+        assert!(!journal_path.exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_interrupted_without_journal() -> anyhow::Result<()> {
+        let segment_path = "test_migrate_interrupted_without_journal.seg";
+        let slots = [legacy_slot(20, 100, 0, 0), legacy_slot(0, 3996, 0, 0)];
+        let legacy = create_legacy(segment_path, &slots, 20, 4096)?;
+        let journal_path = journal_path(Path::new(segment_path));
+
+        // Segment cannot be rebuilt once its journal has been removed
+        write_journal(&legacy, &journal_path)?;
+        std::fs::remove_file(&journal_path)?;
+        assert_eq!(
+            ShmObjectPool::open(segment_path).err(),
+            Some(ShmError::MigrationFailed(
+                "previous migration has been interrupted".into()
+            ))
+        );
+
+        Ok(())
+    }
+}
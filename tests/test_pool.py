@@ -156,6 +156,27 @@ class TestArrayProxy:
         assert (proxy == proxy2).all()
         assert (arr == proxy2).all()
 
+    def test_pickling_structured_dtype(self):
+        dtype = np.dtype([("x", np.float64), ("y", np.int32), ("name", "U8")])
+        arr = np.zeros(10, dtype=dtype)
+        arr["x"] = np.arange(10)
+        arr["name"] = "point"
+        proxy = pyarraypool.make_transferable(arr)
+
+        proxy2 = pickle.loads(pickle.dumps(proxy))
+        assert proxy2.dtype == dtype
+        assert proxy2.dtype.names == ("x", "y", "name")
+        assert (proxy2 == arr).all()
+
+    def test_pickling_many_dimensions(self):
+        arr = np.arange(2 ** 10).reshape((2,) * 10)
+        assert arr.ndim > pyarraypool.MAX_NDIM
+        proxy = pyarraypool.make_transferable(arr)
+
+        proxy2 = pickle.loads(pickle.dumps(proxy))
+        assert proxy2.shape == arr.shape
+        assert (proxy2 == arr).all()
+
     def test_pickling_slice(self):
         arr = np.arange(1000).reshape((100, 10))
         proxy = pyarraypool.make_transferable(arr)
@@ -187,5 +208,35 @@ class TestArrayProxy:
         pyarraypool.make_transferable(arr, transfer_required=False)
 
 
+
+class TestAlignment:
+    @pytest.fixture(autouse=True)
+    def shm_ctx(self):
+        with pyarraypool.object_pool_context():
+            yield
+
+    @staticmethod
+    def address_of(memview: memoryview) -> int:
+        return np.frombuffer(memview, dtype=np.uint8).ctypes.data
+
+    def test_add_object(self):
+        pool = pyarraypool.get_reusable_pool()
+        memview1 = pool.add_object(1, 3)
+        memview2 = pool.add_object(2, 8, alignment=1024)
+
+        assert len(memview1) == 3
+        assert self.address_of(memview1) % 64 == 0
+        assert len(memview2) == 8
+        assert self.address_of(memview2) % 1024 == 0
+
+    def test_allocate(self):
+        pool = pyarraypool.get_reusable_pool()
+        pool.allocate(3)
+        _, _, memview = pool.allocate(8, alignment=4096)
+
+        assert len(memview) == 8
+        assert self.address_of(memview) % 4096 == 0
+
+
 def add_one(arr, idx):
     arr[idx] += 1